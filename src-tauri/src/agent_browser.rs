@@ -1,5 +1,10 @@
+use base64::Engine;
 use tauri::{Manager, Emitter};
+use tauri::webview::PlatformWebview;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
 
 
 // Helper to find a window by label
@@ -7,8 +12,10 @@ fn get_window(handle: &tauri::AppHandle, label: &str) -> Option<tauri::WebviewWi
     handle.get_webview_window(label)
 }
 
+/// Opens an agent window at `url`. With `visible: false` it is built hidden and never
+/// takes focus, for background scraping; the action commands work the same there.
 #[tauri::command]
-pub async fn agent_spawn(handle: tauri::AppHandle, url: String) -> Result<String, String> {
+pub async fn agent_spawn(handle: tauri::AppHandle, url: String, visible: Option<bool>) -> Result<String, String> {
     let id =  uuid::Uuid::new_v4().to_string();
     let label = format!("agent-{}", id);
     
@@ -21,12 +28,41 @@ pub async fn agent_spawn(handle: tauri::AppHandle, url: String) -> Result<String
     )
     .title("Agent Browser")
     .inner_size(1280.0, 800.0)
+    .visible(visible.unwrap_or(true))
+    .focused(visible.unwrap_or(true))
     .build()
     .map_err(|e| e.to_string())?;
 
     Ok(label)
 }
 
+/// Loads `url` in an existing agent window. Only http(s) is allowed, plus `file://` when
+/// `allow_file` is set; javascript:, data: and custom schemes are refused.
+#[tauri::command]
+pub async fn agent_navigate(
+    handle: tauri::AppHandle,
+    label: String,
+    url: String,
+    allow_file: Option<bool>,
+) -> Result<(), String> {
+    let window = get_window(&handle, &label).ok_or("Window not found")?;
+    let target_url = tauri::Url::parse(&url).map_err(|e| e.to_string())?;
+    match target_url.scheme() {
+        "http" | "https" => {}
+        "file" if allow_file.unwrap_or(false) => {}
+        "file" => return Err("Navigating to file:// URLs is not allowed".to_string()),
+        scheme => return Err(format!("Navigating to {}: URLs is not allowed", scheme)),
+    }
+    window.navigate(target_url).map_err(|e| e.to_string())
+}
+
+/// Where the agent window is now, after any redirects or in-page navigation.
+#[tauri::command]
+pub async fn agent_current_url(handle: tauri::AppHandle, label: String) -> Result<String, String> {
+    let window = get_window(&handle, &label).ok_or("Window not found")?;
+    window.url().map(|url| url.to_string()).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn agent_execute(handle: tauri::AppHandle, label: String, script: String) -> Result<(), String> {
     if let Some(window) = get_window(&handle, &label) {
@@ -37,49 +73,125 @@ pub async fn agent_execute(handle: tauri::AppHandle, label: String, script: Stri
     }
 }
 
-// Helper to wrap script with cursor animation logic
+// Quoted, escaped JS string literal for interpolating caller text into a script
+fn js_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+// Requests waiting on a page to report back, keyed by request id
+type PendingResults = Arc<Mutex<HashMap<String, oneshot::Sender<Result<String, String>>>>>;
+
+pub struct AgentState {
+    pub pending: PendingResults,
+}
+
+const RESULT_TIMEOUT: Duration = Duration::from_secs(20);
+const RESEND_INTERVAL: Duration = Duration::from_millis(500);
+
+// Turns `promise`, a JS expression, into a script that reports how it settled to
+// `agent_result`: the resolved value as a string, or the message of what it threw.
+fn reporting(request_id: &str, promise: &str) -> String {
+    let id = js_string(request_id);
+    format!(r#"
+        ({promise}).then(
+            value => window.__TAURI_INTERNALS__.invoke('agent_result', {{ requestId: {id}, value: value == null ? null : String(value) }}),
+            e => window.__TAURI_INTERNALS__.invoke('agent_result', {{ requestId: {id}, error: String(e && e.message || e) }})
+        );
+    "#)
+}
+
+// Evaluates `promise` in the agent window and waits for its result. Actions are
+// injected once so they never run twice; reads pass `resend` to be injected again
+// every RESEND_INTERVAL, which covers a page that was navigating when the first
+// copy ran. Whichever copy answers first wins.
+async fn eval_for_result(
+    window: &tauri::WebviewWindow,
+    state: &AgentState,
+    promise: &str,
+    resend: bool,
+) -> Result<String, String> {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    let script = reporting(&request_id, promise);
+    let (tx, mut rx) = oneshot::channel();
+    state.pending.lock().unwrap().insert(request_id.clone(), tx);
+
+    let deadline = Instant::now() + RESULT_TIMEOUT;
+    let result = loop {
+        if let Err(e) = window.eval(&script) {
+            if !resend {
+                break Err(e.to_string());
+            }
+            eprintln!("eval error: {}", e);
+        }
+        let wait = if resend {
+            RESEND_INTERVAL
+        } else {
+            deadline.saturating_duration_since(Instant::now())
+        };
+        tokio::select! {
+            res = &mut rx => break res.unwrap_or_else(|_| Err("Request dropped".to_string())),
+            _ = tokio::time::sleep(wait) => {}
+        }
+        if Instant::now() >= deadline {
+            break Err("Timeout waiting for the agent page to respond".to_string());
+        }
+    };
+    state.pending.lock().unwrap().remove(&request_id);
+    result
+}
+
+// JS that creates the agent cursor overlay unless the page already has one, leaving it
+// in `cursor`. Every script that moves the cursor starts with this.
+fn ensure_cursor() -> &'static str {
+    r#"
+    let cursor = document.getElementById('agent-cursor');
+    if (!cursor) {
+        cursor = document.createElement('div');
+        cursor.id = 'agent-cursor';
+        cursor.style.cssText = `
+            position: fixed;
+            top: 0;
+            left: 0;
+            width: 20px;
+            height: 20px;
+            z-index: 2147483647;
+            pointer-events: none;
+            transition: transform 0.8s cubic-bezier(0.22, 1, 0.36, 1);
+            filter: drop-shadow(0 2px 4px rgba(0,0,0,0.2));
+        `;
+        cursor.innerHTML = `
+            <svg width="24" height="24" viewBox="0 0 24 24" fill="none" stroke="black" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="fill: #3b82f6; stroke: white; transform: rotate(-15deg);">
+                <path d="M3 3l7.07 16.97 2.51-7.39 7.39-2.51L3 3z"></path>
+            </svg>
+            <div style="
+                position: absolute;
+                left: 24px;
+                top: 12px;
+                background: #3b82f6;
+                color: white;
+                padding: 2px 6px;
+                border-radius: 4px;
+                font-family: sans-serif;
+                font-size: 10px;
+                font-weight: bold;
+                white-space: nowrap;
+            ">Agent</div>
+        `;
+        document.body.appendChild(cursor);
+    }
+    "#
+}
+
+// Helper to wrap script with cursor animation logic. The result is a promise
+// expression for `eval_for_result`; it rejects when nothing matches `selector`.
 fn with_cursor(selector: &str, action_script: &str) -> String {
     format!(r#"
         (async function() {{
             // 1. Ensure Cursor Exists
-            let cursor = document.getElementById('agent-cursor');
-            if (!cursor) {{
-                cursor = document.createElement('div');
-                cursor.id = 'agent-cursor';
-                cursor.style.cssText = `
-                    position: fixed;
-                    top: 0;
-                    left: 0;
-                    width: 20px;
-                    height: 20px;
-                    z-index: 2147483647;
-                    pointer-events: none;
-                    transition: transform 0.8s cubic-bezier(0.22, 1, 0.36, 1);
-                    filter: drop-shadow(0 2px 4px rgba(0,0,0,0.2));
-                `;
-                cursor.innerHTML = `
-                    <svg width="24" height="24" viewBox="0 0 24 24" fill="none" stroke="black" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="fill: #3b82f6; stroke: white; transform: rotate(-15deg);">
-                        <path d="M3 3l7.07 16.97 2.51-7.39 7.39-2.51L3 3z"></path>
-                    </svg>
-                    <div style="
-                        position: absolute;
-                        left: 24px;
-                        top: 12px;
-                        background: #3b82f6;
-                        color: white;
-                        padding: 2px 6px;
-                        border-radius: 4px;
-                        font-family: sans-serif;
-                        font-size: 10px;
-                        font-weight: bold;
-                        white-space: nowrap;
-                    ">Agent</div>
-                `;
-                document.body.appendChild(cursor);
-            }}
+            {ensure_cursor}
 
             // 2. Find Element and Calculate Position
-            const el = document.querySelector("{selector}");
+            const el = document.querySelector({selector});
             if (el) {{
                 const rect = el.getBoundingClientRect();
                 // Target center of element
@@ -92,8 +204,10 @@ fn with_cursor(selector: &str, action_script: &str) -> String {
                 // small visual cue for click
                 cursor.querySelector('svg').style.transform = 'rotate(-15deg) scale(1)';
 
-                // 4. Wait for animation
-                await new Promise(r => setTimeout(r, 800));
+                // 4. Wait for animation (not in a hidden window: nobody sees it, and timers there are throttled)
+                if (!document.hidden) {{
+                    await new Promise(r => setTimeout(r, 800));
+                }}
 
                 // 5. Click Effect (Pulse)
                 cursor.querySelector('svg').style.transform = 'rotate(-15deg) scale(0.8)';
@@ -101,165 +215,141 @@ fn with_cursor(selector: &str, action_script: &str) -> String {
 
                 // 6. Execute Action
                 {action_script}
+            }} else {{
+                throw new Error("No element matches selector: " + {selector});
             }}
-        }})();
-    "#, selector = selector.replace("\"", "\\\""), action_script = action_script)
+        }})()
+    "#, ensure_cursor = ensure_cursor(), selector = js_string(selector), action_script = action_script)
 }
 
 #[tauri::command]
-pub async fn agent_click(handle: tauri::AppHandle, label: String, selector: String) -> Result<(), String> {
+pub async fn agent_click(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, AgentState>,
+    label: String,
+    selector: String,
+) -> Result<(), String> {
+    // Deferred so the result is reported before the click runs: a click that navigates
+    // would otherwise unload the page first and the call would time out.
     let action_code = r#"
-        el.click();
-        const mouseEvent = new MouseEvent('click', {
-            view: window,
-            bubbles: true,
-            cancelable: true
-        });
-        el.dispatchEvent(mouseEvent);
+        setTimeout(() => {
+            el.click();
+            const mouseEvent = new MouseEvent('click', {
+                view: window,
+                bubbles: true,
+                cancelable: true
+            });
+            el.dispatchEvent(mouseEvent);
+        }, 0);
     "#;
     
-    let script = with_cursor(&selector, action_code);
-    agent_execute(handle, label, script).await
+    let window = get_window(&handle, &label).ok_or("Window not found")?;
+    eval_for_result(&window, &state, &with_cursor(&selector, action_code), false).await?;
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn agent_type(handle: tauri::AppHandle, label: String, selector: String, text: String) -> Result<(), String> {
+pub async fn agent_type(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, AgentState>,
+    label: String,
+    selector: String,
+    text: String,
+) -> Result<(), String> {
     let action_code = format!(r#"
         el.focus();
-        el.value = "{}";
+        el.value = {};
         el.dispatchEvent(new Event('input', {{ bubbles: true }}));
         el.dispatchEvent(new Event('change', {{ bubbles: true }}));
-    "#, text.replace("\"", "\\\""));
+    "#, js_string(&text));
 
-    let script = with_cursor(&selector, &action_code);
-    agent_execute(handle, label, script).await
+    let window = get_window(&handle, &label).ok_or("Window not found")?;
+    eval_for_result(&window, &state, &with_cursor(&selector, &action_code), false).await?;
+    Ok(())
 }
 
-// Robust content extraction using title-hacking for data return
-
-    
+/// Returns the page's `innerText`, in full, once the body exists.
 #[tauri::command]
-pub async fn agent_get_content(handle: tauri::AppHandle, label: String) -> Result<String, String> {
+pub async fn agent_get_content(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, AgentState>,
+    label: String,
+) -> Result<String, String> {
     let window = get_window(&handle, &label).ok_or("Window not found")?;
-    
-    // Script to extract content via URL Hash
-    // We modify window.location.hash which is accessible via window.url() in Rust.
-    // This bypasses CSP and Title Sync issues.
+
     let script = r#"
-        (function() {
-            try {
-                if (!document.body) return;
-                
-                const content = document.body.innerText || "";
-                // Limit length to ~4000 to avoid URL length issues
-                const safeContent = content.substring(0, 4000); 
-                const payload = encodeURIComponent(safeContent);
-                
-                // Use history API to avoid scrolling to hash or triggering listeners if possible
-                // But simple hash set is more robust across frames
-                // window.location.hash = "AGENT_RES=" + payload;
-                
-                // Construct new URL with hash
-                const newUrl = new URL(window.location.href);
-                newUrl.hash = "AGENT_RES=" + payload;
-                
-                // Replace state to avoid history pollution
-                history.replaceState(null, '', newUrl.toString());
-            } catch (e) {
-                // Report error via hash
-                const newUrl = new URL(window.location.href);
-                newUrl.hash = "AGENT_RES=__ERROR__" + encodeURIComponent(e.toString());
-                history.replaceState(null, '', newUrl.toString());
+        (async function() {
+            if (!document.body) {
+                await new Promise(r => document.addEventListener('DOMContentLoaded', r, { once: true }));
             }
-        })();
+            return document.body.innerText || "";
+        })()
     "#;
-    
-    let start = Instant::now();
-    let timeout = Duration::from_secs(20);
-    
-    while start.elapsed() < timeout {
-        // Continuously inject
-        if let Err(e) = window.eval(script) {
-             eprintln!("eval error: {}", e);
-        }
-
-        // Check URL
-        if let Ok(url) = window.url() {
-            let url_str = url.to_string();
-            if let Some(pos) = url_str.find("#AGENT_RES=") {
-                let raw = &url_str[pos + "#AGENT_RES=".len()..];
-                
-                if raw.starts_with("__ERROR__") {
-                    let err_msg = urlencoding::decode(&raw.trim_start_matches("__ERROR__"))
-                        .map(|s| s.into_owned())
-                        .unwrap_or_else(|_| raw.to_string());
-                    return Err(format!("JS Error collecting content: {}", err_msg));
-                }
+    eval_for_result(&window, &state, script, true)
+        .await
+        .map_err(|e| format!("Error collecting content: {}", e))
+}
 
-                // Decode
-                let decoded = urlencoding::decode(raw)
-                    .map(|s| s.into_owned())
-                    .unwrap_or_else(|_| raw.to_string());
-                
-                // Cleanup hash?
-                let _ = window.eval("history.replaceState(null, '', window.location.pathname + window.location.search);");
-                
-                return Ok(decoded);
-            }
-        }
-        
-        tokio::time::sleep(Duration::from_millis(500)).await;
+/// Called from the agent page by the scripts `eval_for_result` injects. Replies to
+/// unknown or already answered requests are dropped.
+#[tauri::command]
+pub fn agent_result(
+    state: tauri::State<'_, AgentState>,
+    request_id: String,
+    value: Option<String>,
+    error: Option<String>,
+) {
+    if let Some(tx) = state.pending.lock().unwrap().remove(&request_id) {
+        let _ = tx.send(match error {
+            Some(e) => Err(e),
+            None => Ok(value.unwrap_or_default()),
+        });
     }
-
-    Err("Timeout waiting for content. URL hash did not update.".to_string())
 }
 
 #[tauri::command]
-pub async fn agent_scroll(handle: tauri::AppHandle, label: String, selector: String) -> Result<(), String> {
-    // For scroll, we might not want to move the cursor to the element BEFORE scrolling, 
-    // because the element might be off-screen.
-    // So we scroll first, THEN maybe move cursor? 
-    // Actually, let's just use the standard wrapper which will try its best.
-    // If the element is off-screen, getBoundingClientRect returns off-screen coords.
-    // The cursor might fly off-screen.
-    // But `el.scrollIntoView` is called in the action script.
-    
-    // Better strategy for scroll:
-    // 1. Scroll
-    // 2. Move cursor to it
-    
+pub async fn agent_scroll(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, AgentState>,
+    label: String,
+    selector: String,
+) -> Result<(), String> {
+    // Scroll first and only then move the cursor, so it never flies to off-screen coordinates
     let script = format!(r#"
         (async function() {{
-             // Ensure Cursor
-            let cursor = document.getElementById('agent-cursor');
-            if (!cursor) {{
-                 cursor = document.createElement('div');
-                 cursor.id = 'agent-cursor';
-                 // ... (inject style same as above for consistency, abbreviated here but should be full)
-                 cursor.style.cssText = `position: fixed; top: 0; left: 0; width: 20px; height: 20px; z-index: 2147483647; pointer-events: none; transition: transform 0.8s cubic-bezier(0.22, 1, 0.36, 1); filter: drop-shadow(0 2px 4px rgba(0,0,0,0.2));`;
-                 cursor.innerHTML = `<svg width="24" height="24" viewBox="0 0 24 24" fill="none" stroke="black" stroke-width="2" stroke-linecap="round" stroke-linejoin="round" style="fill: #3b82f6; stroke: white; transform: rotate(-15deg);"><path d="M3 3l7.07 16.97 2.51-7.39 7.39-2.51L3 3z"></path></svg><div style="position: absolute; left: 24px; top: 12px; background: #3b82f6; color: white; padding: 2px 6px; border-radius: 4px; font-family: sans-serif; font-size: 10px; font-weight: bold; white-space: nowrap;">Agent</div>`;
-                 document.body.appendChild(cursor);
-            }}
+            {ensure_cursor}
 
-            const el = document.querySelector("{}");
+            const el = document.querySelector({selector});
             if (el) {{
-                el.scrollIntoView({{ behavior: 'smooth', block: 'center' }});
+                // A hidden window may never run the smooth animation, so jump there instead
+                el.scrollIntoView({{ behavior: document.hidden ? 'auto' : 'smooth', block: 'center' }});
                 
                 // Update cursor pos after scroll
-                await new Promise(r => setTimeout(r, 500));
+                if (!document.hidden) {{
+                    await new Promise(r => setTimeout(r, 500));
+                }}
                 const rect = el.getBoundingClientRect();
                 const x = rect.left + (rect.width / 2);
                 const y = rect.top + (rect.height / 2);
                 cursor.style.transform = `translate(${{x}}px, ${{y}}px)`;
+            }} else {{
+                throw new Error("No element matches selector: " + {selector});
             }}
-        }})();
-    "#, selector.replace("\"", "\\\""));
+        }})()
+    "#, ensure_cursor = ensure_cursor(), selector = js_string(&selector));
     
-    agent_execute(handle, label, script).await
+    let window = get_window(&handle, &label).ok_or("Window not found")?;
+    eval_for_result(&window, &state, &script, false).await?;
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn agent_hover(handle: tauri::AppHandle, label: String, selector: String) -> Result<(), String> {
+pub async fn agent_hover(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, AgentState>,
+    label: String,
+    selector: String,
+) -> Result<(), String> {
     let action_code = r#"
         const mouseover = new MouseEvent('mouseover', {
             view: window,
@@ -275,8 +365,9 @@ pub async fn agent_hover(handle: tauri::AppHandle, label: String, selector: Stri
         el.dispatchEvent(mouseenter);
     "#;
     
-    let script = with_cursor(&selector, action_code);
-    agent_execute(handle, label, script).await
+    let window = get_window(&handle, &label).ok_or("Window not found")?;
+    eval_for_result(&window, &state, &with_cursor(&selector, action_code), false).await?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -288,3 +379,185 @@ pub async fn agent_close(handle: tauri::AppHandle, label: String) -> Result<(),
         Err("Window not found".to_string())
     }
 }
+
+type CaptureSender = oneshot::Sender<Result<Vec<u8>, String>>;
+
+/// Captures what the agent window is currently showing, as a base64-encoded PNG. Uses the
+/// webview's own snapshot, falling back to capturing the OS window if that fails.
+#[tauri::command]
+pub async fn agent_screenshot(handle: tauri::AppHandle, label: String) -> Result<String, String> {
+    let window = get_window(&handle, &label).ok_or("Window not found")?;
+
+    let (tx, rx) = oneshot::channel();
+    let snapshot = match window.with_webview(move |webview| capture_webview(webview, tx)) {
+        Ok(()) => match tokio::time::timeout(RESULT_TIMEOUT, rx).await {
+            Ok(Ok(png)) => png,
+            Ok(Err(_)) => Err("Screenshot was cancelled".to_string()),
+            Err(_) => Err("Timeout waiting for screenshot".to_string()),
+        },
+        Err(e) => Err(e.to_string()),
+    };
+
+    let png = match snapshot {
+        Ok(png) => png,
+        Err(e) => {
+            let title = window.title().map_err(|e| e.to_string())?;
+            let position = window.outer_position().map_err(|e| e.to_string())?;
+            let scale = window.scale_factor().map_err(|e| e.to_string())?;
+            tokio::task::spawn_blocking(move || capture_window(&title, (position.x, position.y), scale))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|fallback| format!("{}; window capture failed too: {}", e, fallback))?
+        }
+    };
+    Ok(base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+// Fallback that grabs the window as the OS composites it. Unlike the webview snapshot it
+// needs the window on screen and unobscured, so a hidden agent window can't be captured.
+fn capture_window(title: &str, position: (i32, i32), scale: f64) -> Result<Vec<u8>, String> {
+    let pid = std::process::id();
+    let windows = xcap::Window::all().map_err(|e| e.to_string())?;
+    let target = windows
+        .into_iter()
+        .filter(|w| {
+            w.pid().ok() == Some(pid)
+                && w.title().is_ok_and(|t| t == title)
+                && !w.is_minimized().unwrap_or(false)
+        })
+        // Agent windows share a title, so pick the one at our position. Some platforms
+        // report logical points rather than physical pixels; whichever is closer wins.
+        .min_by_key(|w| {
+            let (x, y) = (w.x().unwrap_or(0), w.y().unwrap_or(0));
+            let physical = (x - position.0).abs() + (y - position.1).abs();
+            let (lx, ly) = ((x as f64 * scale) as i32, (y as f64 * scale) as i32);
+            let logical = (lx - position.0).abs() + (ly - position.1).abs();
+            physical.min(logical)
+        })
+        .ok_or("Agent window is not on screen")?;
+
+    let image = target.capture_image().map_err(|e| e.to_string())?;
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), xcap::image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+// The capture_webview variants run on the main thread (via `with_webview`) and start the
+// webview's own snapshot, which renders the page itself rather than grabbing the screen,
+// so an occluded or off-screen window still captures correctly. Each answers on `tx`
+// once the platform's completion callback fires.
+
+#[cfg(target_os = "linux")]
+fn capture_webview(webview: PlatformWebview, tx: CaptureSender) {
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+
+    webview.inner().snapshot(
+        SnapshotRegion::Visible,
+        SnapshotOptions::NONE,
+        None::<&gtk::gio::Cancellable>,
+        move |result| {
+            let png = result.map_err(|e| e.to_string()).and_then(|surface| {
+                let image = cairo::ImageSurface::try_from(surface)
+                    .map_err(|_| "Snapshot is not an image surface".to_string())?;
+                let mut png = Vec::new();
+                image.write_to_png(&mut png).map_err(|e| e.to_string())?;
+                Ok(png)
+            });
+            let _ = tx.send(png);
+        },
+    );
+}
+
+#[cfg(target_os = "macos")]
+fn capture_webview(webview: PlatformWebview, tx: CaptureSender) {
+    use block2::RcBlock;
+    use objc2_app_kit::{NSBitmapImageFileType, NSBitmapImageRep, NSImage};
+    use objc2_foundation::{NSDictionary, NSError};
+    use objc2_web_kit::WKWebView;
+
+    // The block type is Fn, so the one-shot sender has to be taken out of a cell
+    let tx = Mutex::new(Some(tx));
+    let handler = RcBlock::new(move |image: *mut NSImage, error: *mut NSError| {
+        // SAFETY: WebKit passes either a valid image or a valid error, the other nil
+        let png = match unsafe { image.as_ref() } {
+            Some(image) => image
+                .TIFFRepresentation()
+                .and_then(|tiff| NSBitmapImageRep::imageRepWithData(&tiff))
+                .and_then(|rep| unsafe {
+                    rep.representationUsingType_properties(NSBitmapImageFileType::PNG, &NSDictionary::new())
+                })
+                .map(|data| data.to_vec())
+                .ok_or_else(|| "Could not encode snapshot as PNG".to_string()),
+            None => Err(unsafe { error.as_ref() }
+                .map_or("Snapshot failed".to_string(), |e| e.localizedDescription().to_string())),
+        };
+        if let Some(tx) = tx.lock().unwrap().take() {
+            let _ = tx.send(png);
+        }
+    });
+
+    // SAFETY: on macOS `inner()` is the WKWebView, and `with_webview` runs us on the main thread
+    unsafe {
+        let view = &*(webview.inner() as *const WKWebView);
+        view.takeSnapshotWithConfiguration_completionHandler(None, &handler);
+    }
+}
+
+#[cfg(windows)]
+fn capture_webview(webview: PlatformWebview, tx: CaptureSender) {
+    use webview2_com::CapturePreviewCompletedHandler;
+    use webview2_com::Microsoft::Web::WebView2::Win32::COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG;
+    use windows::Win32::System::Com::{IStream, STREAM_SEEK_SET};
+    use windows::Win32::UI::Shell::SHCreateMemStream;
+
+    fn read_stream(stream: &IStream) -> windows::core::Result<Vec<u8>> {
+        let mut png = Vec::new();
+        let mut chunk = [0u8; 64 * 1024];
+        unsafe {
+            stream.Seek(0, STREAM_SEEK_SET, None)?;
+            loop {
+                let mut read = 0u32;
+                stream.Read(chunk.as_mut_ptr().cast(), chunk.len() as u32, Some(&mut read as *mut u32)).ok()?;
+                if read == 0 {
+                    break;
+                }
+                png.extend_from_slice(&chunk[..read as usize]);
+            }
+        }
+        Ok(png)
+    }
+
+    let Some(stream) = (unsafe { SHCreateMemStream(None) }) else {
+        let _ = tx.send(Err("Could not allocate an image stream".to_string()));
+        return;
+    };
+
+    // Shared so whichever of the callback and the synchronous failure path runs answers
+    let reply = Arc::new(Mutex::new(Some(tx)));
+    let on_done = reply.clone();
+    let done_stream = stream.clone();
+    let handler = CapturePreviewCompletedHandler::create(Box::new(move |result| {
+        let png = result.and_then(|()| read_stream(&done_stream)).map_err(|e| e.to_string());
+        if let Some(tx) = on_done.lock().unwrap().take() {
+            let _ = tx.send(png);
+        }
+        Ok(())
+    }));
+
+    let started = unsafe { webview.controller().CoreWebView2() }.and_then(|core| unsafe {
+        core.CapturePreview(COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG, &stream, &handler)
+    });
+    if let Err(e) = started {
+        if let Some(tx) = reply.lock().unwrap().take() {
+            let _ = tx.send(Err(e.to_string()));
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn capture_webview(_webview: PlatformWebview, tx: CaptureSender) {
+    // No webview snapshot API here; agent_screenshot falls back to the window capture
+    let _ = tx.send(Err("Webview snapshots are not supported on this platform".to_string()));
+}
@@ -1,24 +1,125 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::process::Stdio;
-use serde::Serialize;
-use tokio::io::{AsyncReadExt, BufReader};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::Command;
-use tauri::State;
+use tokio::task::JoinHandle;
+use tauri::{AppHandle, Emitter, State};
 use uuid::Uuid;
 
 // Structure to hold process state
 pub struct BackgroundProcess {
     child: Option<tokio::process::Child>, // Option so we can take it when finished
+    os_pid: Option<u32>, // the wrapper's pid (its process group on unix); child.id() is gone once reaped
     stdout_buffer: Arc<Mutex<Vec<u8>>>,
     stderr_buffer: Arc<Mutex<Vec<u8>>>,
+    combined_buffer: Option<Arc<Mutex<Vec<u8>>>>, // stdout + stderr in arrival order
+    readers: Vec<JoinHandle<()>>, // the tasks filling the buffers; finished or aborted before "completed"
     is_finished: bool,
     exit_code: Option<i32>,
+    command: String,
+    started_at: i64, // unix millis
+}
+
+const READER_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+// Gives the readers a moment to reach EOF so output written just before exit isn't
+// lost, then aborts whatever is left (a grandchild holding the pipes open) so nothing
+// appends to the buffers after the process is reported done.
+async fn finish_readers(readers: Vec<JoinHandle<()>>) {
+    let deadline = tokio::time::Instant::now() + READER_DRAIN_TIMEOUT;
+    for mut reader in readers {
+        if tokio::time::timeout_at(deadline, &mut reader).await.is_err() {
+            reader.abort();
+        }
+    }
+}
+
+fn snapshot(pid: &str, proc: &BackgroundProcess, since: CmdOffsets) -> CmdResult {
+    collect_result(
+        pid,
+        &proc.stdout_buffer,
+        &proc.stderr_buffer,
+        &proc.combined_buffer,
+        proc.is_finished,
+        proc.exit_code,
+        since,
+    )
+}
+
+// Decodes `buf` from `offset` on and returns the text with the offset it ends at.
+// While the process can still write, a trailing partial character is held back for
+// the next read rather than decoded as U+FFFD.
+fn read_from(buf: &[u8], offset: usize, complete: bool) -> (String, usize) {
+    let start = offset.min(buf.len());
+    let mut end = buf.len();
+    if !complete {
+        if let Err(e) = std::str::from_utf8(&buf[start..]) {
+            if e.error_len().is_none() {
+                end = start + e.valid_up_to();
+            }
+        }
+    }
+    (String::from_utf8_lossy(&buf[start..end]).to_string(), end)
+}
+
+fn collect_result(
+    pid: &str,
+    stdout_buf: &Mutex<Vec<u8>>,
+    stderr_buf: &Mutex<Vec<u8>>,
+    combined_buf: &Option<Arc<Mutex<Vec<u8>>>>,
+    finished: bool,
+    exit_code: Option<i32>,
+    since: CmdOffsets,
+) -> CmdResult {
+    let (stdout, stdout_end) = read_from(&stdout_buf.lock().unwrap(), since.stdout, finished);
+    let (stderr, stderr_end) = read_from(&stderr_buf.lock().unwrap(), since.stderr, finished);
+    let combined = combined_buf
+        .as_ref()
+        .map(|buf| read_from(&buf.lock().unwrap(), since.combined, finished));
+
+    CmdResult {
+        status: if finished { "completed" } else { "running" }.to_string(),
+        pid: Some(pid.to_string()),
+        stdout,
+        stderr,
+        offsets: CmdOffsets {
+            stdout: stdout_end,
+            stderr: stderr_end,
+            combined: combined.as_ref().map_or(0, |(_, end)| *end),
+        },
+        combined: combined.map(|(text, _)| text),
+        exit_code,
+    }
 }
 
 // Global state container
 pub struct ProcessState {
     pub processes: Arc<Mutex<HashMap<String, BackgroundProcess>>>,
+    pub multiplex: Arc<AtomicBool>, // also emit every chunk on the shared `cmd-output` event
+}
+
+#[derive(Serialize, Clone)]
+struct CmdOutputEvent {
+    pid: String,
+    stream: &'static str, // "stdout" | "stderr"
+    data: String,
+}
+
+// Live output goes out on `cmd-output:{pid}`, and on `cmd-output` for the
+// combined panel once something has subscribed to it.
+fn emit_output(app: &AppHandle, multiplex: &AtomicBool, pid: &str, stream: &'static str, data: &[u8]) {
+    let event = CmdOutputEvent {
+        pid: pid.to_string(),
+        stream,
+        data: String::from_utf8_lossy(data).to_string(),
+    };
+    if multiplex.load(Ordering::Relaxed) {
+        let _ = app.emit("cmd-output", event.clone());
+    }
+    let _ = app.emit(&format!("cmd-output:{}", pid), event);
 }
 
 #[derive(Serialize)]
@@ -27,17 +128,52 @@ pub struct CmdResult {
     pub pid: Option<String>,
     pub stdout: String,
     pub stderr: String,
+    pub combined: Option<String>,
     pub exit_code: Option<i32>,
+    pub offsets: CmdOffsets, // where each stream's text above ends; pass back as `since_offset`
+}
+
+/// Byte offsets into a command's stdout, stderr and combined buffers.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(default)]
+pub struct CmdOffsets {
+    pub stdout: usize,
+    pub stderr: usize,
+    pub combined: usize,
 }
 
+// Append a chunk to the merged transcript, tagged with its stream and optionally a timestamp
+fn append_combined(combined: &Option<Arc<Mutex<Vec<u8>>>>, tag: &str, timestamps: bool, data: &[u8]) {
+    if let Some(buf) = combined {
+        let mut buf = buf.lock().unwrap();
+        if timestamps {
+            let ts = chrono::Local::now().format("%H:%M:%S%.3f");
+            buf.extend_from_slice(format!("[{}] [{}] ", ts, tag).as_bytes());
+        }
+        buf.extend_from_slice(data);
+    }
+}
+
+/// Runs `command` through `sh -c` (`cmd /C` on Windows), so it is subject to that shell's
+/// quoting and expansion; callers must quote arguments themselves. `env` is added on top
+/// of the inherited environment, and `stdin`, when given, is written to the child and
+/// then closed.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn exec_background_cmd(
+    app: AppHandle,
     state: State<'_, ProcessState>, 
     command: String, 
     cwd: String, 
-    timeout_ms: Option<u64>
+    timeout_ms: Option<u64>,
+    combined: Option<bool>,
+    timestamps: Option<bool>,
+    env: Option<HashMap<String, String>>,
+    stdin: Option<String>,
 ) -> Result<CmdResult, String> {
     let timeout_val = timeout_ms.unwrap_or(5000); // Default 5s
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let timestamps = timestamps.unwrap_or(false);
     
     // Prepare command
     let mut cmd = if cfg!(target_os = "windows") {
@@ -55,11 +191,29 @@ pub async fn exec_background_cmd(
     if !cwd.is_empty() {
         cmd.current_dir(&cwd);
     }
+    if let Some(env) = env {
+        cmd.envs(env);
+    }
+    if stdin.is_some() {
+        cmd.stdin(Stdio::piped());
+    }
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
 
+    // Own process group, so kill_background_cmd can take down whatever the shell started
+    #[cfg(unix)]
+    cmd.process_group(0);
+
     // Spawn
     let mut child = cmd.spawn().map_err(|e| format!("Failed to spawn: {}", e))?;
+
+    // Feed stdin from its own task so a payload larger than the pipe buffer can't stall
+    // us while the child blocks on a full stdout; dropping the handle sends EOF.
+    if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+        tokio::spawn(async move {
+            let _ = pipe.write_all(input.as_bytes()).await;
+        });
+    }
     
     let stdout = child.stdout.take().unwrap();
     let stderr = child.stderr.take().unwrap();
@@ -71,8 +225,23 @@ pub async fn exec_background_cmd(
     let out_clone = stdout_buf.clone();
     let err_clone = stderr_buf.clone();
 
+    // Optional merged stream: both readers append to one buffer under a single lock,
+    // so the transcript keeps the true interleaving of stdout and stderr.
+    let combined_buf = if combined.unwrap_or(false) {
+        Some(Arc::new(Mutex::new(Vec::new())))
+    } else {
+        None
+    };
+    let out_combined = combined_buf.clone();
+    let err_combined = combined_buf.clone();
+
+    // Generate PID up front so the readers can label their events
+    let pid = Uuid::new_v4().to_string();
+    let (out_app, out_pid, out_mux) = (app.clone(), pid.clone(), state.multiplex.clone());
+    let (err_app, err_pid, err_mux) = (app, pid.clone(), state.multiplex.clone());
+
     // Spawn background readers
-    tokio::spawn(async move {
+    let stdout_reader = tokio::spawn(async move {
         let mut reader = BufReader::new(stdout);
         let mut buf = [0; 1024];
         loop {
@@ -81,13 +250,15 @@ pub async fn exec_background_cmd(
                 Ok(n) => {
                     let mut file = out_clone.lock().unwrap();
                     file.extend_from_slice(&buf[..n]);
+                    append_combined(&out_combined, "stdout", timestamps, &buf[..n]);
+                    emit_output(&out_app, &out_mux, &out_pid, "stdout", &buf[..n]);
                 }
                 Err(_) => break,
             }
         }
     });
 
-    tokio::spawn(async move {
+    let stderr_reader = tokio::spawn(async move {
         let mut reader = BufReader::new(stderr);
         let mut buf = [0; 1024];
         loop {
@@ -96,15 +267,14 @@ pub async fn exec_background_cmd(
                 Ok(n) => {
                     let mut file = err_clone.lock().unwrap();
                     file.extend_from_slice(&buf[..n]);
+                    append_combined(&err_combined, "stderr", timestamps, &buf[..n]);
+                    emit_output(&err_app, &err_mux, &err_pid, "stderr", &buf[..n]);
                 }
                 Err(_) => break,
             }
         }
     });
 
-    // Generate PID
-    let pid = Uuid::new_v4().to_string();
-
     // Check with timeout
     let timeout_duration = std::time::Duration::from_millis(timeout_val);
     
@@ -119,17 +289,17 @@ pub async fn exec_background_cmd(
             // Finished within timeout
             match status_res {
                 Ok(status) => {
-                    // Read whatever is in buffers
-                    let stdout_out = String::from_utf8_lossy(&stdout_buf.lock().unwrap()).to_string();
-                    let stderr_out = String::from_utf8_lossy(&stderr_buf.lock().unwrap()).to_string();
-                    
-                    Ok(CmdResult {
-                        status: "completed".to_string(),
-                        pid: Some(pid),
-                        stdout: stdout_out,
-                        stderr: stderr_out,
-                        exit_code: status.code(),
-                    })
+                    finish_readers(vec![stdout_reader, stderr_reader]).await;
+
+                    Ok(collect_result(
+                        &pid,
+                        &stdout_buf,
+                        &stderr_buf,
+                        &combined_buf,
+                        true,
+                        status.code(),
+                        CmdOffsets::default(),
+                    ))
                 }
                 Err(e) => Err(format!("Process error: {}", e))
             }
@@ -138,88 +308,149 @@ pub async fn exec_background_cmd(
             // Timed out, store process
             let mut processes = state.processes.lock().unwrap();
             processes.insert(pid.clone(), BackgroundProcess {
+                os_pid: child.id(),
                 child: Some(child),
                 stdout_buffer: stdout_buf.clone(),
                 stderr_buffer: stderr_buf.clone(),
+                combined_buffer: combined_buf.clone(),
+                readers: vec![stdout_reader, stderr_reader],
                 is_finished: false,
                 exit_code: None,
+                command,
+                started_at,
             });
             
             // Get partial output
-            let stdout_out = String::from_utf8_lossy(&stdout_buf.lock().unwrap()).to_string();
-            let stderr_out = String::from_utf8_lossy(&stderr_buf.lock().unwrap()).to_string();
-
-            Ok(CmdResult {
-                status: "running".to_string(),
-                pid: Some(pid),
-                stdout: stdout_out,
-                stderr: stderr_out,
-                exit_code: None,
-            })
+            Ok(collect_result(
+                &pid,
+                &stdout_buf,
+                &stderr_buf,
+                &combined_buf,
+                false,
+                None,
+                CmdOffsets::default(),
+            ))
         }
     }
 }
 
+/// Polls a background command. With `since_offset` (the `offsets` of an earlier result)
+/// only output that arrived after it is returned, so the caller can append deltas.
 #[tauri::command]
-pub async fn check_background_cmd(state: State<'_, ProcessState>, pid: String) -> Result<CmdResult, String> {
-    let mut processes = state.processes.lock().unwrap();
-    let proc = processes.get_mut(&pid).ok_or("Process not found")?;
-    
-    if proc.is_finished {
-        return Ok(CmdResult {
-            status: "completed".to_string(),
-            pid: Some(pid),
-            stdout: String::from_utf8_lossy(&proc.stdout_buffer.lock().unwrap()).to_string(),
-            stderr: String::from_utf8_lossy(&proc.stderr_buffer.lock().unwrap()).to_string(),
-            exit_code: proc.exit_code,
-        });
-    }
+pub async fn check_background_cmd(
+    state: State<'_, ProcessState>,
+    pid: String,
+    since_offset: Option<CmdOffsets>,
+) -> Result<CmdResult, String> {
+    let since = since_offset.unwrap_or_default();
+    // Poll under the lock, but drain the readers of a just-finished process without it
+    let exited = {
+        let mut processes = state.processes.lock().unwrap();
+        let proc = processes.get_mut(&pid).ok_or("Process not found")?;
+        if proc.is_finished {
+            return Ok(snapshot(&pid, proc, since));
+        }
 
-    // Check if finished now
-    // We need to destructively check child if we want to use `.try_wait()` on tokio::process::Child?
-    // standard Tokio Child try_wait is: `pub fn try_wait(&mut self) -> Result<Option<ExitStatus>>`
-    // So we need mutable access to child.
-    
-    if let Some(child) = &mut proc.child {
+        let child = proc.child.as_mut().ok_or("Invalid process state")?;
         match child.try_wait() {
-            Ok(Some(status)) => {
-                proc.is_finished = true;
-                proc.exit_code = status.code();
-                
-                Ok(CmdResult {
-                    status: "completed".to_string(),
-                    pid: Some(pid),
-                    stdout: String::from_utf8_lossy(&proc.stdout_buffer.lock().unwrap()).to_string(),
-                    stderr: String::from_utf8_lossy(&proc.stderr_buffer.lock().unwrap()).to_string(),
-                    exit_code: proc.exit_code,
-                })
-            },
-            Ok(None) => {
-                // Still running
-                Ok(CmdResult {
-                    status: "running".to_string(),
-                    pid: Some(pid),
-                    stdout: String::from_utf8_lossy(&proc.stdout_buffer.lock().unwrap()).to_string(),
-                    stderr: String::from_utf8_lossy(&proc.stderr_buffer.lock().unwrap()).to_string(),
-                    exit_code: None,
-                })
-            },
-            Err(e) => Err(format!("Error checking process: {}", e))
+            Ok(Some(status)) => Some((status.code(), std::mem::take(&mut proc.readers))),
+            Ok(None) => None,
+            Err(e) => return Err(format!("Error checking process: {}", e)),
         }
-    } else {
-        Err("Invalid process state".to_string())
+    };
+
+    if let Some((code, readers)) = exited {
+        finish_readers(readers).await;
+        let mut processes = state.processes.lock().unwrap();
+        let proc = processes.get_mut(&pid).ok_or("Process not found")?;
+        proc.is_finished = true;
+        proc.exit_code = code;
+        return Ok(snapshot(&pid, proc, since));
     }
+
+    let processes = state.processes.lock().unwrap();
+    let proc = processes.get(&pid).ok_or("Process not found")?;
+    Ok(snapshot(&pid, proc, since))
+}
+
+#[derive(Serialize)]
+pub struct CmdInfo {
+    pub pid: String,
+    pub status: String, // "completed" | "running"
+    pub command: String,
+    pub started_at: i64,
 }
 
+/// Every command still held in `ProcessState`, oldest first, so the UI can show what is
+/// running and offer to kill anything it lost track of. Output stays with
+/// `check_background_cmd`, which also finalizes commands that have exited.
 #[tauri::command]
-pub async fn kill_background_cmd(state: State<'_, ProcessState>, pid: String) -> Result<(), String> {
-    let child_opt = {
-        let mut processes = state.processes.lock().unwrap();
-        processes.remove(&pid).and_then(|mut p| p.child.take())
-    };
+pub fn list_background_cmds(state: State<'_, ProcessState>) -> Vec<CmdInfo> {
+    let mut processes = state.processes.lock().unwrap();
+    let mut list: Vec<CmdInfo> = processes
+        .iter_mut()
+        .map(|(pid, proc)| {
+            let exited = proc.is_finished
+                || proc.child.as_mut().is_some_and(|c| matches!(c.try_wait(), Ok(Some(_))));
+            CmdInfo {
+                pid: pid.clone(),
+                status: if exited { "completed" } else { "running" }.to_string(),
+                command: proc.command.clone(),
+                started_at: proc.started_at,
+            }
+        })
+        .collect();
+    list.sort_by_key(|info| info.started_at);
+    list
+}
+
+// Kills the whole tree under the `sh -c` / `cmd /C` wrapper rather than just the
+// wrapper, so a dev server or watcher it started doesn't outlive it. Uses the pid
+// recorded at spawn, since the wrapper may already have exited and been reaped while
+// what it started is still running.
+async fn kill_tree(os_pid: Option<u32>, child: Option<tokio::process::Child>) {
+    if let Some(id) = os_pid {
+        #[cfg(unix)]
+        // SAFETY: plain syscall; the group was created at spawn with the child as leader
+        unsafe {
+            libc::kill(-(id as libc::pid_t), libc::SIGKILL);
+        }
 
-    if let Some(mut child) = child_opt {
+        #[cfg(target_os = "windows")]
+        {
+            let mut taskkill = Command::new("taskkill");
+            taskkill.args(["/T", "/F", "/PID", &id.to_string()]);
+            taskkill.creation_flags(0x08000000); // CREATE_NO_WINDOW
+            let _ = taskkill.output().await;
+        }
+    }
+    // Reap the wrapper (and kill it, if the group signal somehow missed it)
+    if let Some(mut child) = child {
         let _ = child.kill().await;
     }
+}
+
+#[tauri::command]
+pub async fn kill_background_cmd(state: State<'_, ProcessState>, pid: String) -> Result<(), String> {
+    let proc = state.processes.lock().unwrap().remove(&pid);
+
+    if let Some(mut proc) = proc {
+        // Stop the readers first so none of them outlives the process it was reading
+        for reader in &proc.readers {
+            reader.abort();
+        }
+        kill_tree(proc.os_pid, proc.child.take()).await;
+    }
     Ok(())
 }
+
+/// Turns on the shared `cmd-output` event so one listener sees every command's output.
+#[tauri::command]
+pub fn subscribe_all_cmd_output(state: State<'_, ProcessState>) {
+    state.multiplex.store(true, Ordering::Relaxed);
+}
+
+#[tauri::command]
+pub fn unsubscribe_all_cmd_output(state: State<'_, ProcessState>) {
+    state.multiplex.store(false, Ordering::Relaxed);
+}
@@ -0,0 +1,262 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub enum DapTransport {
+    Tcp(TcpStream),
+    Stdio(Child),
+}
+
+pub struct DapSession {
+    pub writer: Box<dyn Write + Send>,
+    pub transport: DapTransport,
+    token: Arc<()>, // identifies this session, so a reader only ever removes its own entry
+}
+
+type Sessions = Arc<Mutex<HashMap<String, DapSession>>>;
+
+pub struct DapState {
+    pub sessions: Sessions,
+}
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn close_session(session: DapSession) {
+    match session.transport {
+        DapTransport::Tcp(stream) => {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        DapTransport::Stdio(mut child) => {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct DapDataEvent {
+    id: String,
+    message: String,
+}
+
+#[derive(Serialize, Clone)]
+struct DapTerminatedEvent {
+    id: String,
+}
+
+// DAP uses the same Content-Length framing as LSP. BufReader keeps any bytes past
+// the current body, so several messages arriving in one read are emitted one by one.
+fn read_framed<Rd: Read>(reader: Rd, mut on_message: impl FnMut(String)) {
+    let mut reader = BufReader::new(reader);
+    loop {
+        let mut content_length: usize = 0;
+        loop {
+            let mut header = String::new();
+            match reader.read_line(&mut header) {
+                Ok(0) => return,
+                Err(_) => return,
+                _ => {}
+            }
+            let trimmed = header.trim();
+            if trimmed.is_empty() {
+                break;
+            }
+            if let Some(len_str) = trimmed.strip_prefix("Content-Length: ") {
+                content_length = len_str.parse().unwrap_or(0);
+            }
+        }
+
+        if content_length == 0 {
+            continue;
+        }
+
+        let mut body = vec![0u8; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+
+        on_message(String::from_utf8_lossy(&body).to_string());
+    }
+}
+
+// Forward framed messages to the frontend; the events are identical for every transport.
+// When the stream ends the session is removed and closed (reaping a stdio adapter),
+// unless it was already disconnected or the id now belongs to a newer session.
+fn spawn_reader<R: Runtime, Rd: Read + Send + 'static>(
+    app: AppHandle<R>,
+    sessions: Sessions,
+    id: String,
+    token: Arc<()>,
+    reader: Rd,
+) {
+    thread::spawn(move || {
+        read_framed(reader, |message| {
+            let _ = app.emit(
+                "dap-data",
+                DapDataEvent {
+                    id: id.clone(),
+                    message,
+                },
+            );
+        });
+
+        let ended = {
+            let mut sessions = sessions.lock().unwrap();
+            match sessions.get(&id) {
+                Some(session) if Arc::ptr_eq(&session.token, &token) => sessions.remove(&id),
+                _ => None,
+            }
+        };
+        if let Some(session) = ended {
+            close_session(session);
+        }
+        let _ = app.emit("dap-terminated", DapTerminatedEvent { id });
+    });
+}
+
+// Tries each address `host` resolves to, giving up on each after CONNECT_TIMEOUT
+fn connect_with_timeout(host: &str, port: u16) -> std::io::Result<TcpStream> {
+    let mut last_err = None;
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| std::io::Error::other("no addresses resolved")))
+}
+
+#[tauri::command(async)]
+pub fn dap_connect<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, DapState>,
+    id: String,
+    host: String,
+    port: u16,
+) -> Result<(), String> {
+    if state.sessions.lock().unwrap().contains_key(&id) {
+        return Err(format!("Debug session {} already running", id));
+    }
+
+    // Connect without the lock so a dead host can't stall every other session
+    let stream = connect_with_timeout(&host, port)
+        .map_err(|e| format!("Failed to connect to {}:{}: {}", host, port, e))?;
+    let reader = stream.try_clone().map_err(|e| e.to_string())?;
+    let writer = stream.try_clone().map_err(|e| e.to_string())?;
+
+    let mut sessions = state.sessions.lock().unwrap();
+    if sessions.contains_key(&id) {
+        let _ = stream.shutdown(Shutdown::Both);
+        return Err(format!("Debug session {} already running", id));
+    }
+
+    let token = Arc::new(());
+    sessions.insert(
+        id.clone(),
+        DapSession {
+            writer: Box::new(writer),
+            transport: DapTransport::Tcp(stream),
+            token: token.clone(),
+        },
+    );
+
+    spawn_reader(app, state.sessions.clone(), id, token, reader);
+    Ok(())
+}
+
+/// Launches a debug adapter that speaks DAP over stdin/stdout (debugpy, lldb-vscode, ...).
+#[tauri::command]
+pub fn dap_spawn<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, DapState>,
+    id: String,
+    command: String,
+    args: Vec<String>,
+    cwd: Option<String>,
+) -> Result<(), String> {
+    let mut sessions = state.sessions.lock().unwrap();
+    if sessions.contains_key(&id) {
+        return Err(format!("Debug session {} already running", id));
+    }
+
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        use std::os::windows::process::CommandExt;
+        let mut c = Command::new("cmd");
+        let mut full_args = vec!["/C".to_string(), command.clone()];
+        full_args.extend(args.clone());
+        c.args(&full_args);
+        c.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        c
+    };
+
+    #[cfg(not(target_os = "windows"))]
+    let mut cmd = {
+        let mut c = Command::new(&command);
+        c.args(&args);
+        c
+    };
+
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+
+    if let Some(ref cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn {}: {}", command, e))?;
+
+    let stdin = child.stdin.take().ok_or("Failed to capture stdin")?;
+    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+
+    let token = Arc::new(());
+    sessions.insert(
+        id.clone(),
+        DapSession {
+            writer: Box::new(stdin),
+            transport: DapTransport::Stdio(child),
+            token: token.clone(),
+        },
+    );
+
+    spawn_reader(app, state.sessions.clone(), id, token, stdout);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn dap_send(state: tauri::State<'_, DapState>, id: String, message: String) -> Result<(), String> {
+    let mut sessions = state.sessions.lock().unwrap();
+    let session = sessions
+        .get_mut(&id)
+        .ok_or(format!("Debug session {} not found", id))?;
+
+    let header = format!("Content-Length: {}\r\n\r\n", message.len());
+    session
+        .writer
+        .write_all(header.as_bytes())
+        .map_err(|e| e.to_string())?;
+    session
+        .writer
+        .write_all(message.as_bytes())
+        .map_err(|e| e.to_string())?;
+    session.writer.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn dap_disconnect(state: tauri::State<'_, DapState>, id: String) -> Result<(), String> {
+    let session = state.sessions.lock().unwrap().remove(&id);
+    if let Some(session) = session {
+        close_session(session);
+    }
+    Ok(())
+}
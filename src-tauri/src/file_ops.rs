@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// Resolves `path` (which need not exist yet) through its parent directory and checks
+// that it lies strictly inside `root`. The last component is not followed, so a
+// symlink is operated on itself rather than on its target.
+fn resolve_within(root: &str, path: &str) -> Result<PathBuf, String> {
+    let root = fs::canonicalize(root).map_err(|e| format!("{}: {}", root, e))?;
+    let p = Path::new(path);
+
+    let name = match p.components().next_back() {
+        Some(Component::Normal(name)) => name,
+        _ => return Err(format!("Invalid path: {}", path)),
+    };
+    let parent = p.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let resolved = fs::canonicalize(parent)
+        .map_err(|e| format!("{}: {}", parent.display(), e))?
+        .join(name);
+
+    if !resolved.starts_with(&root) || resolved == root {
+        return Err(format!("{} is outside the project", path));
+    }
+    Ok(resolved)
+}
+
+fn is_cross_device(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::CrossesDevices
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    let meta = fs::symlink_metadata(from)?;
+    if meta.file_type().is_symlink() {
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(fs::read_link(from)?, to);
+        #[cfg(not(unix))]
+        return fs::copy(from, to).map(|_| ());
+    }
+    if meta.is_dir() {
+        fs::create_dir(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        return fs::set_permissions(to, meta.permissions());
+    }
+    fs::copy(from, to).map(|_| ())
+}
+
+fn remove_any(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Renames or moves a file or directory. Both paths must be inside `root`, and `to`
+/// must not exist yet. Moves across filesystems fall back to copy-then-delete.
+#[tauri::command(async)]
+pub fn rename_path(root: String, from: String, to: String) -> Result<(), String> {
+    let from = resolve_within(&root, &from)?;
+    let to = resolve_within(&root, &to)?;
+
+    fs::symlink_metadata(&from).map_err(|e| format!("{}: {}", from.display(), e))?;
+    if fs::symlink_metadata(&to).is_ok() {
+        return Err(format!("{} already exists", to.display()));
+    }
+    if to.starts_with(&from) {
+        return Err("Cannot move a directory into itself".into());
+    }
+
+    match fs::rename(&from, &to) {
+        Ok(()) => Ok(()),
+        Err(e) if is_cross_device(&e) => {
+            if let Err(e) = copy_recursive(&from, &to) {
+                let _ = remove_any(&to);
+                return Err(e.to_string());
+            }
+            remove_any(&from).map_err(|e| e.to_string())
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Deletes a file or directory inside `root`. With `to_trash` it goes to the OS trash
+/// instead of being unlinked.
+#[tauri::command(async)]
+pub fn delete_path(root: String, path: String, to_trash: bool) -> Result<(), String> {
+    let path = resolve_within(&root, &path)?;
+    fs::symlink_metadata(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    if to_trash {
+        trash::delete(&path).map_err(|e| e.to_string())
+    } else {
+        remove_any(&path).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub enum PathKind {
+    File,
+    Dir,
+    Symlink, // reported for the link itself, even when dangling
+    None,
+}
+
+/// Creates `path` and any missing parents. Succeeds if the directory already exists.
+#[tauri::command]
+pub fn create_directory(path: String) -> Result<(), String> {
+    if let Ok(meta) = fs::metadata(&path) {
+        if !meta.is_dir() {
+            return Err(format!("{} already exists as a file", path));
+        }
+    }
+    fs::create_dir_all(&path).map_err(|e| e.to_string())
+}
+
+/// What, if anything, is at `path`, without following a final symlink.
+#[tauri::command]
+pub fn path_exists(path: String) -> PathKind {
+    match fs::symlink_metadata(&path) {
+        Ok(m) if m.file_type().is_symlink() => PathKind::Symlink,
+        Ok(m) if m.is_dir() => PathKind::Dir,
+        Ok(_) => PathKind::File,
+        Err(_) => PathKind::None,
+    }
+}
+
+/// Shows `path` selected in Finder or Explorer. On Linux the file manager is asked over
+/// D-Bus (FileManager1), falling back to opening the containing folder with xdg-open.
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    fs::symlink_metadata(&path).map_err(|e| format!("{}: {}", path, e))?;
+    tauri_plugin_opener::reveal_item_in_dir(&path).map_err(|e| e.to_string())
+}
+
+/// Opens `path` with the OS default application for its type.
+#[tauri::command]
+pub fn open_with_default(path: String) -> Result<(), String> {
+    fs::metadata(&path).map_err(|e| format!("{}: {}", path, e))?;
+    tauri_plugin_opener::open_path(&path, None::<&str>).map_err(|e| e.to_string())
+}
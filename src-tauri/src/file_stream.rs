@@ -0,0 +1,147 @@
+use base64::Engine;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Runtime};
+
+const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
+const MAX_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+// Flow control for one stream. `acked` is the highest offset the viewer has
+// confirmed; the reader stays at most `window` chunks ahead of it.
+pub struct FileStream {
+    cancelled: AtomicBool,
+    window: Option<usize>,
+    acked: Mutex<u64>,
+    ack_signal: Condvar,
+}
+
+pub struct FileStreamState {
+    pub streams: Arc<Mutex<HashMap<String, Arc<FileStream>>>>,
+}
+
+#[derive(Serialize, Clone)]
+struct FileChunkEvent {
+    offset: u64,
+    data: String, // base64
+    done: bool,
+    total_size: Option<u64>, // set on the final event
+    cancelled: bool,
+}
+
+/// Streams a file as base64 `file-chunk:{stream_id}` events, ending with one event where
+/// `done` is true. Listen before calling, or pass your own `stream_id`. With `window`,
+/// the reader waits for `ack_file_chunk` once that many chunks are unacknowledged.
+#[tauri::command]
+pub fn stream_file<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, FileStreamState>,
+    path: String,
+    chunk_size: Option<usize>,
+    stream_id: Option<String>,
+    window: Option<usize>,
+) -> Result<String, String> {
+    let chunk_size = chunk_size
+        .unwrap_or(DEFAULT_CHUNK_SIZE)
+        .clamp(1, MAX_CHUNK_SIZE);
+    let mut file = File::open(&path).map_err(|e| e.to_string())?;
+
+    let id = stream_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let stream = Arc::new(FileStream {
+        cancelled: AtomicBool::new(false),
+        window: window.filter(|w| *w > 0),
+        acked: Mutex::new(0),
+        ack_signal: Condvar::new(),
+    });
+
+    {
+        let mut streams = state.streams.lock().unwrap();
+        if streams.contains_key(&id) {
+            return Err(format!("Stream {} already running", id));
+        }
+        streams.insert(id.clone(), stream.clone());
+    }
+
+    let streams = state.streams.clone();
+    let stream_id = id.clone();
+    thread::spawn(move || {
+        let event = format!("file-chunk:{}", stream_id);
+        let mut buf = vec![0u8; chunk_size];
+        let mut offset: u64 = 0;
+
+        loop {
+            if let Some(window) = stream.window {
+                let ahead_limit = window.saturating_mul(chunk_size) as u64;
+                let mut acked = stream.acked.lock().unwrap();
+                while offset.saturating_sub(*acked) >= ahead_limit && !stream.cancelled.load(Ordering::Relaxed) {
+                    // Timed wait so a cancel without a final ack can't strand the thread
+                    acked = stream
+                        .ack_signal
+                        .wait_timeout(acked, Duration::from_millis(500))
+                        .unwrap()
+                        .0;
+                }
+            }
+            if stream.cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let n = match file.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            let _ = app.emit(
+                &event,
+                FileChunkEvent {
+                    offset,
+                    data: base64::engine::general_purpose::STANDARD.encode(&buf[..n]),
+                    done: false,
+                    total_size: None,
+                    cancelled: false,
+                },
+            );
+            offset += n as u64;
+        }
+
+        streams.lock().unwrap().remove(&stream_id);
+        let _ = app.emit(
+            &event,
+            FileChunkEvent {
+                offset,
+                data: String::new(),
+                done: true,
+                total_size: Some(offset),
+                cancelled: stream.cancelled.load(Ordering::Relaxed),
+            },
+        );
+    });
+
+    Ok(id)
+}
+
+/// Tells a windowed stream that everything before `offset` has been consumed.
+#[tauri::command]
+pub fn ack_file_chunk(state: tauri::State<'_, FileStreamState>, stream_id: String, offset: u64) -> Result<(), String> {
+    let streams = state.streams.lock().unwrap();
+    if let Some(stream) = streams.get(&stream_id) {
+        let mut acked = stream.acked.lock().unwrap();
+        *acked = (*acked).max(offset);
+        stream.ack_signal.notify_all();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn cancel_stream(state: tauri::State<'_, FileStreamState>, stream_id: String) -> Result<(), String> {
+    let streams = state.streams.lock().unwrap();
+    if let Some(stream) = streams.get(&stream_id) {
+        stream.cancelled.store(true, Ordering::Relaxed);
+        stream.ack_signal.notify_all();
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Runtime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct FileWatcherState {
+    pub watchers: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+#[derive(Serialize, Clone)]
+struct FileChangedEvent {
+    path: String,
+    exists: bool,
+    size: u64,
+    modified: Option<i64>, // epoch millis
+}
+
+// Snapshot of the metadata we compare between polls
+#[derive(PartialEq, Clone, Copy)]
+struct FileStamp {
+    exists: bool,
+    size: u64,
+    modified: Option<i64>,
+}
+
+fn stamp(path: &str) -> FileStamp {
+    match fs::metadata(path) {
+        Ok(meta) => FileStamp {
+            exists: true,
+            size: meta.len(),
+            modified: meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64),
+        },
+        Err(_) => FileStamp {
+            exists: false,
+            size: 0,
+            modified: None,
+        },
+    }
+}
+
+// Tauri event names only allow alphanumerics and `-`, `/`, `:`, `_`. Sanitizing alone
+// would give `a b.rs` and `a_b.rs` the same name, so a hash of the full path keeps it unique.
+fn event_name(path: &str) -> String {
+    let safe: String = path
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let hash = crate::sha256_hex(path.as_bytes());
+    format!("file-externally-changed:{}-{}", safe, &hash[..16])
+}
+
+/// Starts watching a single file and returns the event name that changes are emitted on.
+/// The editor stops it with `unwatch_file` when the last tab showing the file closes.
+#[tauri::command]
+pub fn watch_file<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, FileWatcherState>,
+    path: String,
+) -> Result<String, String> {
+    let event = event_name(&path);
+
+    let mut watchers = state.watchers.lock().unwrap();
+    if watchers.contains_key(&path) {
+        return Ok(event);
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    watchers.insert(path.clone(), stop.clone());
+
+    let event_clone = event.clone();
+    thread::spawn(move || {
+        let mut last = stamp(&path);
+        let mut pending: Option<FileStamp> = None;
+
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(POLL_INTERVAL);
+            let current = stamp(&path);
+
+            // Debounce: only report once the file has settled for a full poll,
+            // so a burst of writes from one external save yields one event.
+            match pending {
+                Some(p) if p == current => {
+                    pending = None;
+                    last = current;
+                    let _ = app.emit(
+                        &event_clone,
+                        FileChangedEvent {
+                            path: path.clone(),
+                            exists: current.exists,
+                            size: current.size,
+                            modified: current.modified,
+                        },
+                    );
+                }
+                _ if current != last => pending = Some(current),
+                _ => pending = None,
+            }
+        }
+    });
+
+    Ok(event)
+}
+
+#[tauri::command]
+pub fn unwatch_file(state: tauri::State<'_, FileWatcherState>, path: String) -> Result<(), String> {
+    let mut watchers = state.watchers.lock().unwrap();
+    if let Some(stop) = watchers.remove(&path) {
+        stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
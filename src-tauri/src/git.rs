@@ -1,5 +1,8 @@
-use git2::{DiffOptions, Repository, StatusOptions};
-use serde::Serialize;
+use git2::{Cred, CredentialType, DiffOptions, RemoteCallbacks, Repository, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Runtime};
 
 #[derive(Serialize, Clone)]
 pub struct FileStatus {
@@ -12,7 +15,12 @@ pub struct CommitEntry {
     pub hash: String,
     pub message: String,
     pub author: String,
-    pub date: String,
+    pub author_email: String,
+    pub committer: String,
+    pub committer_email: String,
+    pub date: String, // RFC3339 with the committer's offset
+    pub timestamp: i64,
+    pub offset_minutes: i32,
     pub parent_hashes: Vec<String>,
 }
 
@@ -21,12 +29,26 @@ pub struct CommitDetails {
     pub hash: String,
     pub message: String,
     pub author: String,
-    pub date: String,
+    pub author_email: String,
+    pub committer: String,
+    pub committer_email: String,
+    pub date: String, // RFC3339 with the committer's offset
+    pub timestamp: i64,
+    pub offset_minutes: i32,
     pub files_changed: usize,
     pub insertions: usize,
     pub deletions: usize,
 }
 
+// RFC3339 in the timezone recorded in the commit, e.g. 2024-05-01T14:03:00+02:00
+fn format_git_time(time: git2::Time) -> String {
+    let offset = chrono::FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
+    chrono::DateTime::from_timestamp(time.seconds(), 0)
+        .map(|dt| dt.with_timezone(&offset).to_rfc3339())
+        .unwrap_or_else(|| time.seconds().to_string())
+}
+
 #[tauri::command]
 pub fn git_status(path: String) -> Result<Vec<FileStatus>, String> {
     let repo = Repository::discover(&path).map_err(|e| e.to_string())?;
@@ -68,20 +90,166 @@ pub fn git_status(path: String) -> Result<Vec<FileStatus>, String> {
     Ok(results)
 }
 
+#[derive(Serialize, Clone)]
+pub struct DirtyState {
+    pub dirty: bool,
+    pub class: String, // "clean" | "has_changes"
+}
+
+/// Cheap yes/no for "are there uncommitted changes". Staged changes are found by
+/// comparing HEAD with the index, which never touches the working tree; only if
+/// that is clean do we stat the worktree, without recursing into untracked dirs
+/// or detecting renames.
+#[tauri::command]
+pub fn git_is_dirty(repo_path: String) -> Result<DirtyState, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    let result = |dirty: bool| DirtyState {
+        dirty,
+        class: if dirty { "has_changes" } else { "clean" }.to_string(),
+    };
+
+    // Unborn HEAD: anything in the index counts as a change
+    let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+    let index = repo.index().map_err(|e| e.to_string())?;
+    let staged = repo
+        .diff_tree_to_index(head_tree.as_ref(), Some(&index), None)
+        .map_err(|e| e.to_string())?;
+    if staged.deltas().len() > 0 {
+        return Ok(result(true));
+    }
+
+    let mut opts = StatusOptions::new();
+    opts.show(git2::StatusShow::Workdir)
+        .include_untracked(true)
+        .recurse_untracked_dirs(false)
+        .exclude_submodules(true)
+        .renames_index_to_workdir(false);
+
+    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
+    Ok(result(!statuses.is_empty()))
+}
+
+/// How git relates to a single path: "tracked-clean", "tracked-modified", "staged",
+/// "untracked" or "ignored". Unlike `git_status` this also reports clean and ignored files.
+#[tauri::command]
+pub fn git_path_tracking(repo_path: String, file_path: String) -> Result<String, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Not a working directory")?;
+
+    let abs_path = std::path::Path::new(&file_path);
+    let rel_path = abs_path.strip_prefix(workdir).map_err(|e| e.to_string())?;
+
+    // Ignore rules only apply to untracked paths, so they're checked last. status_file only
+    // works on files; a directory counts as tracked if the index has anything under it
+    if abs_path.is_dir() {
+        let prefix = format!("{}/", rel_path.to_string_lossy().replace("\\", "/"));
+        let index = repo.index().map_err(|e| e.to_string())?;
+        let state = if index.iter().any(|e| e.path.starts_with(prefix.as_bytes())) {
+            "tracked-clean"
+        } else if repo.is_path_ignored(rel_path).map_err(|e| e.to_string())? {
+            "ignored"
+        } else {
+            "untracked"
+        };
+        return Ok(state.to_string());
+    }
+
+    let status = repo.status_file(rel_path).map_err(|e| e.to_string())?;
+    let state = if status.is_ignored() {
+        "ignored"
+    } else if status.is_wt_new() {
+        if repo.is_path_ignored(rel_path).map_err(|e| e.to_string())? {
+            "ignored"
+        } else {
+            "untracked"
+        }
+    } else if status.intersects(
+        git2::Status::INDEX_NEW
+            | git2::Status::INDEX_MODIFIED
+            | git2::Status::INDEX_DELETED
+            | git2::Status::INDEX_RENAMED
+            | git2::Status::INDEX_TYPECHANGE,
+    ) {
+        "staged"
+    } else if status.intersects(
+        git2::Status::WT_MODIFIED
+            | git2::Status::WT_DELETED
+            | git2::Status::WT_RENAMED
+            | git2::Status::WT_TYPECHANGE,
+    ) {
+        "tracked-modified"
+    } else {
+        "tracked-clean"
+    };
+    Ok(state.to_string())
+}
+
+fn resolve_tree<'r>(repo: &'r Repository, rev: &str) -> Result<git2::Tree<'r>, String> {
+    repo.revparse_single(rev)
+        .and_then(|obj| obj.peel_to_tree())
+        .map_err(|e| format!("{}: {}", rev, e))
+}
+
+// Builds the diff for one of the supported modes:
+//   "worktree"     - index -> working directory (unstaged changes, the default)
+//   "staged"       - HEAD -> index
+//   "commit_range" - from_rev -> to_rev (to_rev defaults to HEAD)
+fn build_diff<'r>(
+    repo: &'r Repository,
+    mode: Option<&str>,
+    from_rev: Option<&str>,
+    to_rev: Option<&str>,
+    opts: &mut DiffOptions,
+) -> Result<git2::Diff<'r>, String> {
+    match mode.unwrap_or("worktree") {
+        "worktree" => {
+            let index = repo.index().map_err(|e| e.to_string())?;
+            repo.diff_index_to_workdir(Some(&index), Some(opts))
+                .map_err(|e| e.to_string())
+        }
+        "staged" => {
+            let index = repo.index().map_err(|e| e.to_string())?;
+            // Unborn HEAD: everything in the index is staged
+            let head_tree = match repo.head() {
+                Ok(head) => Some(head.peel_to_tree().map_err(|e| e.to_string())?),
+                Err(_) => None,
+            };
+            repo.diff_tree_to_index(head_tree.as_ref(), Some(&index), Some(opts))
+                .map_err(|e| e.to_string())
+        }
+        "commit_range" => {
+            let from = from_rev.ok_or("commit_range requires from_rev")?;
+            let old_tree = resolve_tree(repo, from)?;
+            let new_tree = resolve_tree(repo, to_rev.unwrap_or("HEAD"))?;
+            repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(opts))
+                .map_err(|e| e.to_string())
+        }
+        other => Err(format!("Unknown diff mode: {}", other)),
+    }
+}
+
 #[tauri::command]
-pub fn git_diff(repo_path: String, file_path: String) -> Result<String, String> {
+pub fn git_diff(
+    repo_path: String,
+    file_path: String,
+    mode: Option<String>,
+    from_rev: Option<String>,
+    to_rev: Option<String>,
+) -> Result<String, String> {
     let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
 
-    // Check if the file exists in HEAD (to diff against)
-    // For simplicity, we diff index to workdir for now (unstaged changes)
     let mut opts = DiffOptions::new();
     opts.pathspec(&file_path);
     opts.context_lines(3);
 
-    let index = repo.index().map_err(|e| e.to_string())?;
-    let diff = repo
-        .diff_index_to_workdir(Some(&index), Some(&mut opts))
-        .map_err(|e| e.to_string())?;
+    let diff = build_diff(
+        &repo,
+        mode.as_deref(),
+        from_rev.as_deref(),
+        to_rev.as_deref(),
+        &mut opts,
+    )?;
 
     let mut diff_str = String::new();
     diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
@@ -100,11 +268,115 @@ pub fn git_diff(repo_path: String, file_path: String) -> Result<String, String>
     Ok(diff_str)
 }
 
+#[derive(Serialize, Clone)]
+pub enum DiffLineOrigin {
+    Context,
+    Added,
+    Deleted,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DiffLine {
+    pub origin: DiffLineOrigin,
+    pub content: String,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Same inputs as `git_diff`, but returns parsed hunks instead of a patch string.
+#[tauri::command]
+pub fn git_diff_structured(
+    repo_path: String,
+    file_path: String,
+    mode: Option<String>,
+    from_rev: Option<String>,
+    to_rev: Option<String>,
+) -> Result<Vec<DiffHunk>, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    let mut opts = DiffOptions::new();
+    opts.pathspec(&file_path);
+    opts.context_lines(3);
+
+    let diff = build_diff(
+        &repo,
+        mode.as_deref(),
+        from_rev.as_deref(),
+        to_rev.as_deref(),
+        &mut opts,
+    )?;
+
+    let mut hunks = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let patch = match git2::Patch::from_diff(&diff, idx).map_err(|e| e.to_string())? {
+            Some(p) => p,
+            None => continue, // binary or unchanged
+        };
+
+        for h in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(h).map_err(|e| e.to_string())?;
+            let mut lines = Vec::with_capacity(line_count);
+
+            for l in 0..line_count {
+                let line = patch.line_in_hunk(h, l).map_err(|e| e.to_string())?;
+                let origin = match line.origin() {
+                    '+' => DiffLineOrigin::Added,
+                    '-' => DiffLineOrigin::Deleted,
+                    ' ' => DiffLineOrigin::Context,
+                    _ => continue, // "no newline at end of file" markers
+                };
+                lines.push(DiffLine {
+                    origin,
+                    content: String::from_utf8_lossy(line.content()).to_string(),
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                });
+            }
+
+            hunks.push(DiffHunk {
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+    }
+
+    Ok(hunks)
+}
+
+/// Only the not-yet-staged portion of a file's changes: the index (staged version)
+/// compared against the working tree. For a partially staged file this is the
+/// "unstaged" pane, alongside HEAD -> index (`git_diff` mode "staged").
+#[tauri::command]
+pub fn git_diff_unstaged_changes(repo_path: String, file_path: String) -> Result<Vec<DiffHunk>, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    // Absolute to relative so the pathspec matches exactly one file
+    let workdir = repo.workdir().ok_or("Not a working directory")?;
+    let abs_path = std::path::Path::new(&file_path);
+    let rel_path = abs_path.strip_prefix(workdir).unwrap_or(abs_path);
+    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
+
+    git_diff_structured(repo_path, rel_path_str, Some("worktree".to_string()), None, None)
+}
+
 #[tauri::command]
 pub fn git_log(
     repo_path: String,
     limit: usize,
     file_filter: Option<String>,
+    skip: Option<usize>,
 ) -> Result<Vec<CommitEntry>, String> {
     let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
     let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
@@ -117,6 +389,8 @@ pub fn git_log(
 
     let mut commits = Vec::new();
     let mut count = 0;
+    let mut skipped = 0;
+    let skip = skip.unwrap_or(0);
 
     // Better approach: Use `git` CLI if possible? No, we want to stay in Rust.
     // Let's implement the diff check.
@@ -185,26 +459,149 @@ pub fn git_log(
             }
         }
 
-        let author = commit.author();
-        let date = commit.time();
-
-        // Format date simply for now
-        let date_str = format!("{}", date.seconds());
-
-        commits.push(CommitEntry {
-            hash: commit.id().to_string(),
-            message: commit.message().unwrap_or("").to_string(),
-            author: author.name().unwrap_or("Unknown").to_string(),
-            date: date_str,
-            parent_hashes: commit.parent_ids().map(|id| id.to_string()).collect(),
-        });
+        // Pagination: only commits that passed the filter count towards the offset
+        if skipped < skip {
+            skipped += 1;
+            continue;
+        }
 
+        commits.push(commit_entry(&commit));
         count += 1;
     }
 
     Ok(commits)
 }
 
+fn commit_entry(commit: &git2::Commit) -> CommitEntry {
+    let author = commit.author();
+    let committer = commit.committer();
+    let date = commit.time();
+
+    CommitEntry {
+        hash: commit.id().to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        author: author.name().unwrap_or("Unknown").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        committer: committer.name().unwrap_or("Unknown").to_string(),
+        committer_email: committer.email().unwrap_or("").to_string(),
+        date: format_git_time(date),
+        timestamp: date.seconds(),
+        offset_minutes: date.offset_minutes(),
+        parent_hashes: commit.parent_ids().map(|id| id.to_string()).collect(),
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct HistoryMatch {
+    #[serde(flatten)]
+    pub commit: CommitEntry,
+    pub files: Vec<String>, // repo-relative paths whose change matched
+}
+
+// -S semantics: the number of occurrences differs between the old and new blob
+fn pickaxe_count(repo: &Repository, id: git2::Oid, needle: &[u8]) -> usize {
+    if id.is_zero() {
+        return 0;
+    }
+    let blob = match repo.find_blob(id) {
+        Ok(b) if !b.is_binary() => b,
+        _ => return 0,
+    };
+    let content = blob.content();
+    content.windows(needle.len()).filter(|w| *w == needle).count()
+}
+
+/// Code archaeology: commits whose diff adds or removes `needle` (like `git log -S`),
+/// or, with `is_regex`, whose added/removed lines match it (like `git log -G`).
+#[tauri::command(async)]
+pub fn git_search_history(
+    repo_path: String,
+    needle: String,
+    is_regex: Option<bool>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryMatch>, String> {
+    if needle.is_empty() {
+        return Err("Search string is empty".to_string());
+    }
+    let regex = if is_regex.unwrap_or(false) {
+        Some(regex::bytes::Regex::new(&needle).map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+    let limit = limit.unwrap_or(100);
+
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk.push_head().map_err(|e| e.to_string())?;
+    revwalk
+        .set_sorting(git2::Sort::TIME)
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for oid in revwalk {
+        if results.len() >= limit {
+            break;
+        }
+
+        let oid = oid.map_err(|e| e.to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+
+        // Root commits are compared against the empty tree
+        let parent_tree = if commit.parent_count() > 0 {
+            Some(commit.parent(0).and_then(|p| p.tree()).map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+        let tree = commit.tree().map_err(|e| e.to_string())?;
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+            .map_err(|e| e.to_string())?;
+
+        let mut files: Vec<String> = Vec::new();
+        match &regex {
+            Some(re) => {
+                diff.foreach(
+                    &mut |_, _| true,
+                    None,
+                    None,
+                    Some(&mut |delta, _hunk, line| {
+                        if matches!(line.origin(), '+' | '-') && re.is_match(line.content()) {
+                            if let Some(path) = delta.new_file().path().or(delta.old_file().path()) {
+                                let path = path.to_string_lossy().replace("\\", "/");
+                                if !files.contains(&path) {
+                                    files.push(path);
+                                }
+                            }
+                        }
+                        true
+                    }),
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            None => {
+                for delta in diff.deltas() {
+                    let before = pickaxe_count(&repo, delta.old_file().id(), needle.as_bytes());
+                    let after = pickaxe_count(&repo, delta.new_file().id(), needle.as_bytes());
+                    if before != after {
+                        if let Some(path) = delta.new_file().path().or(delta.old_file().path()) {
+                            files.push(path.to_string_lossy().replace("\\", "/"));
+                        }
+                    }
+                }
+            }
+        }
+
+        if !files.is_empty() {
+            results.push(HistoryMatch {
+                commit: commit_entry(&commit),
+                files,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
 #[tauri::command]
 pub fn git_read_file(path: String, revision: String) -> Result<String, String> {
     let repo = Repository::discover(&path).map_err(|e| e.to_string())?;
@@ -224,98 +621,731 @@ pub fn git_read_file(path: String, revision: String) -> Result<String, String> {
     Ok(content.to_string())
 }
 
+#[derive(Serialize, Clone)]
+pub struct BlobBytes {
+    pub data: String, // base64
+    pub size: usize,  // full blob size, even when `data` is truncated
+    pub truncated: bool,
+}
+
+/// Raw contents of a file at a revision, for binaries that `git_read_file` can't decode.
 #[tauri::command]
-pub fn git_stage(repo_path: String, file_path: String) -> Result<(), String> {
-    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
-    let mut index = repo.index().map_err(|e| e.to_string())?;
+pub fn git_read_blob_bytes(
+    repo_path: String,
+    file_path: String,
+    revision: String,
+    max_bytes: Option<usize>,
+) -> Result<BlobBytes, String> {
+    use base64::Engine;
 
-    // Absolute to relative
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
     let workdir = repo.workdir().ok_or("Not a working directory")?;
+
+    // Convert absolute path to relative path for git
     let abs_path = std::path::Path::new(&file_path);
     let rel_path = abs_path.strip_prefix(workdir).map_err(|e| e.to_string())?;
+    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
+
+    let spec = format!("{}:{}", revision, rel_path_str);
+    let obj = repo.revparse_single(&spec).map_err(|e| e.to_string())?;
+    let blob = obj.as_blob().ok_or("Object is not a blob")?;
+
+    let content = blob.content();
+    let limit = max_bytes.unwrap_or(content.len()).min(content.len());
+
+    Ok(BlobBytes {
+        data: base64::engine::general_purpose::STANDARD.encode(&content[..limit]),
+        size: content.len(),
+        truncated: limit < content.len(),
+    })
+}
+
+/// Error returned by mutating commands, describing what state the repo was left in.
+#[derive(Serialize, Clone, Debug)]
+pub struct GitOpError {
+    pub message: String,
+    pub index_written: bool, // the index file changed before the failure
+    pub lock_held: bool,     // .git/index.lock exists or the error was lock contention
+    pub recovery: String,
+}
+
+impl GitOpError {
+    fn plain(err: impl std::fmt::Display) -> Self {
+        GitOpError {
+            message: err.to_string(),
+            index_written: false,
+            lock_held: false,
+            recovery: "No changes were made. Check the repository path and retry.".to_string(),
+        }
+    }
+
+    // Refused before touching the repo, with a specific hint for the user
+    fn rejected(message: &str, recovery: &str) -> Self {
+        GitOpError {
+            message: message.to_string(),
+            index_written: false,
+            lock_held: false,
+            recovery: recovery.to_string(),
+        }
+    }
+}
+
+// Snapshot of the index taken before a mutating operation, used to classify failures
+struct GitOpContext {
+    git_dir: std::path::PathBuf,
+    index_mtime: Option<std::time::SystemTime>,
+}
+
+impl GitOpContext {
+    fn begin(repo: &Repository) -> Self {
+        let git_dir = repo.path().to_path_buf();
+        let index_mtime = std::fs::metadata(git_dir.join("index"))
+            .and_then(|m| m.modified())
+            .ok();
+        GitOpContext { git_dir, index_mtime }
+    }
+
+    fn fail(&self, err: impl std::fmt::Display) -> GitOpError {
+        let message = err.to_string();
+        let lock_held = self.git_dir.join("index.lock").exists() || message.contains("index.lock");
+        let index_mtime = std::fs::metadata(self.git_dir.join("index"))
+            .and_then(|m| m.modified())
+            .ok();
+        let index_written = index_mtime != self.index_mtime;
+
+        let recovery = if lock_held {
+            "Another git process may be running. If none is, delete .git/index.lock and retry."
+        } else if index_written {
+            "The index was partially updated. Review the staged changes before retrying."
+        } else {
+            "No changes were applied. It is safe to retry."
+        };
+
+        GitOpError {
+            message,
+            index_written,
+            lock_held,
+            recovery: recovery.to_string(),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn git_stage(repo_path: String, file_path: String) -> Result<(), GitOpError> {
+    let repo = Repository::discover(&repo_path).map_err(GitOpError::plain)?;
+    let ctx = GitOpContext::begin(&repo);
+    let mut index = repo.index().map_err(|e| ctx.fail(e))?;
+
+    // Absolute to relative
+    let workdir = repo.workdir().ok_or_else(|| ctx.fail("Not a working directory"))?;
+    let abs_path = std::path::Path::new(&file_path);
+    let rel_path = abs_path.strip_prefix(workdir).map_err(|e| ctx.fail(e))?;
 
-    index.add_path(rel_path).map_err(|e| e.to_string())?;
-    index.write().map_err(|e| e.to_string())?;
+    index.add_path(rel_path).map_err(|e| ctx.fail(e))?;
+    index.write().map_err(|e| ctx.fail(e))?;
     Ok(())
 }
 
+// "@@ -12,7 +12,9 @@ fn main" -> (12, 12)
+fn parse_hunk_starts(header: &str) -> Option<(u32, u32)> {
+    let mut parts = header.strip_prefix("@@ ")?.split_whitespace();
+    let start = |part: &str, sign: char| -> Option<u32> {
+        part.strip_prefix(sign)?.split(',').next()?.parse().ok()
+    };
+    let old = start(parts.next()?, '-')?;
+    let new = start(parts.next()?, '+')?;
+    Some((old, new))
+}
+
+/// Stages a single hunk from the working tree, leaving the rest of the file unstaged.
+/// `lines` are raw patch lines (prefixed with ' ', '+' or '-'); the line counts in the
+/// header are recomputed from them, so the caller may drop '+' lines or turn '-' lines
+/// into context to stage only part of a hunk.
 #[tauri::command]
-pub fn git_unstage(repo_path: String, file_path: String) -> Result<(), String> {
-    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+pub fn git_stage_hunk(
+    repo_path: String,
+    file_path: String,
+    hunk_header: String,
+    lines: Vec<String>,
+) -> Result<(), GitOpError> {
+    let repo = Repository::discover(&repo_path).map_err(GitOpError::plain)?;
+    let ctx = GitOpContext::begin(&repo);
 
     // Absolute to relative
-    let workdir = repo.workdir().ok_or("Not a working directory")?;
+    let workdir = repo.workdir().ok_or_else(|| ctx.fail("Not a working directory"))?;
     let abs_path = std::path::Path::new(&file_path);
-    let rel_path = abs_path.strip_prefix(workdir).map_err(|e| e.to_string())?;
+    let rel_path = abs_path.strip_prefix(workdir).map_err(|e| ctx.fail(e))?;
+    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
 
-    // Unstaging is essentially resetting the path in the index to HEAD
-    let head = repo.head().map_err(|e| e.to_string())?;
-    let head_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
-    let head_tree = head_commit.tree().map_err(|e| e.to_string())?;
+    let (old_start, new_start) = parse_hunk_starts(&hunk_header)
+        .ok_or_else(|| GitOpError::plain(format!("Invalid hunk header: {}", hunk_header)))?;
+
+    let mut old_lines = 0;
+    let mut new_lines = 0;
+    let mut body = String::new();
+    for line in &lines {
+        match line.chars().next() {
+            Some(' ') => {
+                old_lines += 1;
+                new_lines += 1;
+            }
+            Some('-') => old_lines += 1,
+            Some('+') => new_lines += 1,
+            Some('\\') => {} // "\ No newline at end of file"
+            _ => return Err(GitOpError::plain(format!("Invalid patch line: {:?}", line))),
+        }
+        body.push_str(line);
+        if !line.ends_with('\n') {
+            body.push('\n');
+        }
+    }
 
-    repo.reset_default(Some(head_tree.as_object()), [rel_path].iter())
-        .map_err(|e| e.to_string())?;
+    // Untracked files have no index entry to patch against
+    let index = repo.index().map_err(|e| ctx.fail(e))?;
+    let header = if index.get_path(rel_path, 0).is_some() {
+        format!(
+            "diff --git a/{0} b/{0}\n--- a/{0}\n+++ b/{0}\n",
+            rel_path_str
+        )
+    } else {
+        format!(
+            "diff --git a/{0} b/{0}\nnew file mode 100644\n--- /dev/null\n+++ b/{0}\n",
+            rel_path_str
+        )
+    };
+    let patch = format!(
+        "{}@@ -{},{} +{},{} @@\n{}",
+        header, old_start, old_lines, new_start, new_lines, body
+    );
+
+    let diff = git2::Diff::from_buffer(patch.as_bytes()).map_err(GitOpError::plain)?;
+    repo.apply(&diff, git2::ApplyLocation::Index, None)
+        .map_err(|e| ctx.fail(e))?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn git_commit(repo_path: String, message: String) -> Result<(), String> {
-    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
-    let mut index = repo.index().map_err(|e| e.to_string())?;
-    let tree_id = index.write_tree().map_err(|e| e.to_string())?;
-    let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+pub fn git_unstage(repo_path: String, file_path: String) -> Result<(), GitOpError> {
+    let repo = Repository::discover(&repo_path).map_err(GitOpError::plain)?;
+    let ctx = GitOpContext::begin(&repo);
 
-    let sig = repo.signature().map_err(|e| e.to_string())?;
+    // Absolute to relative
+    let workdir = repo.workdir().ok_or_else(|| ctx.fail("Not a working directory"))?;
+    let abs_path = std::path::Path::new(&file_path);
+    let rel_path = abs_path.strip_prefix(workdir).map_err(|e| ctx.fail(e))?;
 
-    let head = repo.head().map_err(|e| e.to_string())?;
-    let parent_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+    // Unstaging is essentially resetting the path in the index to HEAD
+    let head = repo.head().map_err(|e| ctx.fail(e))?;
+    let head_commit = head.peel_to_commit().map_err(|e| ctx.fail(e))?;
+    let head_tree = head_commit.tree().map_err(|e| ctx.fail(e))?;
 
-    repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &[&parent_commit])
-        .map_err(|e| e.to_string())?;
+    repo.reset_default(Some(head_tree.as_object()), [rel_path].iter())
+        .map_err(|e| ctx.fail(e))?;
     Ok(())
 }
 
+/// Throws away unstaged edits to a file, like `git checkout -- <file>`. Tracked files
+/// are restored from the index (so staged changes survive); untracked files are deleted.
 #[tauri::command]
-pub fn git_get_branch(repo_path: String) -> Result<String, String> {
-    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
-    let head = repo.head().map_err(|e| e.to_string())?;
+pub fn git_discard_changes(repo_path: String, file_path: String) -> Result<(), GitOpError> {
+    let repo = Repository::discover(&repo_path).map_err(GitOpError::plain)?;
+    let ctx = GitOpContext::begin(&repo);
 
-    if head.is_branch() {
-        Ok(head.shorthand().unwrap_or("unknown").to_string())
-    } else {
-        Ok(head.name().unwrap_or("Detached HEAD").to_string())
+    let workdir = repo.workdir().ok_or_else(|| ctx.fail("Not a working directory"))?;
+    let abs_path = std::path::Path::new(&file_path);
+    let rel_path = abs_path
+        .strip_prefix(workdir)
+        .map_err(|_| GitOpError::plain(format!("{} is outside the repository", file_path)))?;
+    if rel_path
+        .components()
+        .any(|c| matches!(c, std::path::Component::ParentDir))
+    {
+        return Err(GitOpError::plain(format!("{} is outside the repository", file_path)));
+    }
+
+    let status = repo.status_file(rel_path).map_err(|e| ctx.fail(e))?;
+    if status.contains(git2::Status::WT_NEW) {
+        return std::fs::remove_file(abs_path).map_err(GitOpError::plain);
     }
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.path(rel_path).force();
+    repo.checkout_index(None, Some(&mut checkout))
+        .map_err(|e| ctx.fail(e))?;
+    Ok(())
 }
 
-#[derive(Serialize, Clone)]
-pub struct LineDiff {
-    pub line: u32,
-    pub diff_type: String, // "added", "modified", "deleted"
+// Explicit overrides win; otherwise fall back to user.name / user.email from config
+fn commit_signature(
+    repo: &Repository,
+    name: Option<String>,
+    email: Option<String>,
+) -> Result<git2::Signature<'static>, GitOpError> {
+    let configured = repo.signature().ok();
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .or_else(|| configured.as_ref().and_then(|s| s.name().map(|n| n.to_string())));
+    let email = email
+        .filter(|e| !e.trim().is_empty())
+        .or_else(|| configured.as_ref().and_then(|s| s.email().map(|e| e.to_string())));
+
+    match (name, email) {
+        (Some(name), Some(email)) => git2::Signature::now(&name, &email).map_err(GitOpError::plain),
+        _ => Err(GitOpError::rejected(
+            "git identity not configured",
+            "Set user.name and user.email, or provide an author name and email.",
+        )),
+    }
 }
 
 #[tauri::command]
-pub fn git_get_line_diff(repo_path: String, file_path: String) -> Result<Vec<LineDiff>, String> {
-    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+pub fn git_commit(
+    repo_path: String,
+    message: String,
+    author_name: Option<String>,
+    author_email: Option<String>,
+) -> Result<(), GitOpError> {
+    if message.trim().is_empty() {
+        return Err(GitOpError::rejected(
+            "empty commit message",
+            "Write a commit message and try again.",
+        ));
+    }
 
-    // Absolute to relative
-    let workdir = repo.workdir().ok_or("Not a working directory")?;
-    let abs_path = std::path::Path::new(&file_path);
-    let rel_path = abs_path.strip_prefix(workdir).map_err(|e| e.to_string())?;
-    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
+    let repo = Repository::discover(&repo_path).map_err(GitOpError::plain)?;
+    let ctx = GitOpContext::begin(&repo);
+    let mut index = repo.index().map_err(|e| ctx.fail(e))?;
+    let tree_id = index.write_tree().map_err(|e| ctx.fail(e))?;
 
-    let mut opts = DiffOptions::new();
-    opts.pathspec(&rel_path_str);
-    opts.context_lines(0); // We only want changed lines
+    let head = repo.head().map_err(|e| ctx.fail(e))?;
+    let parent_commit = head.peel_to_commit().map_err(|e| ctx.fail(e))?;
 
-    let index = repo.index().map_err(|e| e.to_string())?;
-    let diff = repo
-        .diff_index_to_workdir(Some(&index), Some(&mut opts))
-        .map_err(|e| e.to_string())?;
+    // Same tree as HEAD means the commit would be empty
+    if parent_commit.tree_id() == tree_id {
+        return Err(GitOpError::rejected(
+            "nothing to commit",
+            "Stage some changes before committing.",
+        ));
+    }
 
-    let mut results = Vec::new();
+    let tree = repo.find_tree(tree_id).map_err(|e| ctx.fail(e))?;
+    let sig = commit_signature(&repo, author_name, author_email)?;
 
-    diff.foreach(
-        &mut |_delta, _hunk| true,
+    repo.commit(Some("HEAD"), &sig, &sig, &message, &tree, &[&parent_commit])
+        .map_err(|e| ctx.fail(e))?;
+    Ok(())
+}
+
+/// Rewrites HEAD. A blank `message` keeps the existing one; `include_staged` folds the
+/// current index into the amended commit.
+#[tauri::command]
+pub fn git_commit_amend(
+    repo_path: String,
+    message: Option<String>,
+    include_staged: Option<bool>,
+) -> Result<(), GitOpError> {
+    let repo = Repository::discover(&repo_path).map_err(GitOpError::plain)?;
+    let ctx = GitOpContext::begin(&repo);
+
+    let head = repo.head().map_err(|e| ctx.fail(e))?;
+    let head_commit = head.peel_to_commit().map_err(|e| ctx.fail(e))?;
+
+    if head_commit.parent_count() > 1 {
+        return Err(GitOpError::rejected(
+            "HEAD is a merge commit",
+            "Amending merges is not supported. Use the git CLI if you really need to.",
+        ));
+    }
+
+    // Best effort: only catches pushes the local remote-tracking ref knows about
+    if head.is_branch() {
+        let pushed = head
+            .shorthand()
+            .and_then(|name| repo.find_branch(name, git2::BranchType::Local).ok())
+            .and_then(|branch| branch.upstream().ok())
+            .and_then(|upstream| upstream.get().target())
+            .is_some_and(|up| {
+                up == head_commit.id() || repo.graph_descendant_of(up, head_commit.id()).unwrap_or(false)
+            });
+        if pushed {
+            return Err(GitOpError::rejected(
+                "HEAD has already been pushed",
+                "Amending would rewrite published history. Make a new commit instead.",
+            ));
+        }
+    }
+
+    let tree = if include_staged.unwrap_or(false) {
+        let mut index = repo.index().map_err(|e| ctx.fail(e))?;
+        let tree_id = index.write_tree().map_err(|e| ctx.fail(e))?;
+        Some(repo.find_tree(tree_id).map_err(|e| ctx.fail(e))?)
+    } else {
+        None
+    };
+
+    // Keep the original author, but record who amended it and when
+    let committer = commit_signature(&repo, None, None)?;
+    let message = message.filter(|m| !m.trim().is_empty());
+    head_commit
+        .amend(Some("HEAD"), None, Some(&committer), None, message.as_deref(), tree.as_ref())
+        .map_err(|e| ctx.fail(e))?;
+    Ok(())
+}
+
+// All fields are optional so the frontend only has to send the rules it cares about
+#[derive(Deserialize, Default)]
+pub struct CommitMessageRules {
+    pub max_subject_length: Option<usize>, // defaults to 72
+    pub require_blank_line: Option<bool>,  // defaults to true
+    pub conventional: Option<bool>,        // require `type(scope)!: subject`
+    pub allowed_types: Option<Vec<String>>,
+    pub body_wrap: Option<usize>, // defaults to 72
+}
+
+#[derive(Serialize, Clone)]
+pub struct CommitMessageViolation {
+    pub rule: String,
+    pub message: String,
+    pub line: usize,   // 1-based
+    pub column: usize, // 1-based
+}
+
+#[derive(Serialize, Clone)]
+pub struct CommitMessageReport {
+    pub violations: Vec<CommitMessageViolation>,
+    pub normalized: String,
+}
+
+const CONVENTIONAL_TYPES: &[&str] = &[
+    "feat", "fix", "docs", "style", "refactor", "perf", "test", "build", "ci", "chore", "revert",
+];
+
+// `type(scope)!: description` -> (type, position of the description)
+fn parse_conventional(subject: &str) -> Option<(&str, usize)> {
+    let colon = subject.find(": ")?;
+    let head = subject[..colon].trim_end_matches('!');
+    let ty = match head.find('(') {
+        Some(open) if head.ends_with(')') => &head[..open],
+        Some(_) => return None,
+        None => head,
+    };
+    if ty.is_empty() || !ty.chars().all(|c| c.is_ascii_lowercase()) {
+        return None;
+    }
+    Some((ty, colon + 2))
+}
+
+// Greedy word wrap; words longer than the width are left on their own line
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let indent_len = line.len() - line.trim_start().len();
+    let indent = &line[..indent_len];
+    let mut out = Vec::new();
+    let mut current = String::new();
+
+    for word in line.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            out.push(std::mem::take(&mut current));
+        }
+        if current.is_empty() {
+            current.push_str(indent);
+        } else {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    out.push(current);
+    out
+}
+
+fn normalize_commit_message(message: &str, body_wrap: usize) -> String {
+    let lines: Vec<&str> = message.lines().map(|l| l.trim_end()).collect();
+    let mut iter = lines.iter().skip_while(|l| l.is_empty());
+
+    let subject = match iter.next() {
+        Some(s) => s.trim().to_string(),
+        None => return String::new(),
+    };
+    let body: Vec<&str> = iter.copied().skip_while(|l| l.is_empty()).collect();
+
+    let mut out = subject;
+    if body.is_empty() {
+        out.push('\n');
+        return out;
+    }
+    out.push_str("\n\n");
+
+    let mut wrapped: Vec<String> = Vec::new();
+    for line in body {
+        // Leave comments, lists, quotes, trailers and indented blocks alone
+        let trimmed = line.trim_start();
+        let keep = line.is_empty()
+            || line.starts_with(' ')
+            || line.starts_with('\t')
+            || trimmed.starts_with('#')
+            || trimmed.starts_with('>')
+            || trimmed.starts_with("- ")
+            || trimmed.starts_with("* ")
+            || !trimmed.contains(' ') && trimmed.contains(':');
+        if keep || line.chars().count() <= body_wrap {
+            wrapped.push(line.to_string());
+        } else {
+            wrapped.extend(wrap_line(line, body_wrap));
+        }
+    }
+    // Collapse trailing blank lines
+    while wrapped.last().is_some_and(|l| l.is_empty()) {
+        wrapped.pop();
+    }
+
+    out.push_str(&wrapped.join("\n"));
+    out.push('\n');
+    out
+}
+
+/// Checks a commit message against the given style rules without touching the repo.
+#[tauri::command]
+pub fn validate_commit_message(message: String, rules: Option<CommitMessageRules>) -> CommitMessageReport {
+    let rules = rules.unwrap_or_default();
+    let max_subject = rules.max_subject_length.unwrap_or(72);
+    let body_wrap = rules.body_wrap.unwrap_or(72);
+    let mut violations = Vec::new();
+    let mut push = |rule: &str, message: String, line: usize, column: usize| {
+        violations.push(CommitMessageViolation {
+            rule: rule.to_string(),
+            message,
+            line,
+            column,
+        });
+    };
+
+    let lines: Vec<&str> = message.lines().collect();
+    let subject = lines.first().copied().unwrap_or("");
+
+    if subject.trim().is_empty() {
+        push("subject-empty", "Subject line is empty".to_string(), 1, 1);
+    } else {
+        let len = subject.chars().count();
+        if len > max_subject {
+            push(
+                "subject-length",
+                format!("Subject is {} characters, limit is {}", len, max_subject),
+                1,
+                max_subject + 1,
+            );
+        }
+        if subject.trim_end().ends_with('.') {
+            push(
+                "subject-period",
+                "Subject should not end with a period".to_string(),
+                1,
+                subject.trim_end().chars().count(),
+            );
+        }
+    }
+
+    if rules.require_blank_line.unwrap_or(true) && lines.len() > 1 && !lines[1].trim().is_empty() {
+        push(
+            "blank-line",
+            "Subject must be followed by a blank line".to_string(),
+            2,
+            1,
+        );
+    }
+
+    if rules.conventional.unwrap_or(false) && !subject.trim().is_empty() {
+        match parse_conventional(subject) {
+            None => push(
+                "conventional-format",
+                "Subject must look like `type(scope): description`".to_string(),
+                1,
+                1,
+            ),
+            Some((ty, desc_at)) => {
+                let allowed = match &rules.allowed_types {
+                    Some(types) => types.iter().any(|t| t == ty),
+                    None => CONVENTIONAL_TYPES.contains(&ty),
+                };
+                if !allowed {
+                    push("conventional-type", format!("Unknown commit type `{}`", ty), 1, 1);
+                }
+                if subject[desc_at..].trim().is_empty() {
+                    push(
+                        "conventional-description",
+                        "Description after the type is empty".to_string(),
+                        1,
+                        subject[..desc_at].chars().count() + 1,
+                    );
+                }
+            }
+        }
+    }
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_end();
+        if trimmed.len() != line.len() {
+            push(
+                "trailing-whitespace",
+                "Line has trailing whitespace".to_string(),
+                i + 1,
+                trimmed.chars().count() + 1,
+            );
+        }
+        if i >= 2 && !line.trim_start().starts_with('#') {
+            let len = trimmed.chars().count();
+            if len > body_wrap {
+                push(
+                    "body-length",
+                    format!("Body line is {} characters, limit is {}", len, body_wrap),
+                    i + 1,
+                    body_wrap + 1,
+                );
+            }
+        }
+    }
+
+    CommitMessageReport {
+        violations,
+        normalized: normalize_commit_message(&message, body_wrap),
+    }
+}
+
+#[tauri::command]
+pub fn git_get_branch(repo_path: String) -> Result<String, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let head = repo.head().map_err(|e| e.to_string())?;
+
+    if head.is_branch() {
+        Ok(head.shorthand().unwrap_or("unknown").to_string())
+    } else {
+        Ok(head.name().unwrap_or("Detached HEAD").to_string())
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct BranchInfo {
+    pub name: String,
+    pub is_remote: bool,
+    pub is_head: bool,
+    pub upstream: Option<String>,
+}
+
+#[tauri::command]
+pub fn git_branches(repo_path: String) -> Result<Vec<BranchInfo>, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    // Unborn or detached HEAD: no branch is current
+    let head_name = repo
+        .head()
+        .ok()
+        .filter(|h| h.is_branch())
+        .and_then(|h| h.name().map(|n| n.to_string()));
+
+    let mut branches = Vec::new();
+    for entry in repo.branches(None).map_err(|e| e.to_string())? {
+        let (branch, branch_type) = entry.map_err(|e| e.to_string())?;
+        let name = match branch.name() {
+            Ok(Some(n)) => n.to_string(),
+            _ => continue, // not valid utf-8
+        };
+        // origin/HEAD is a symbolic ref, not a branch anyone wants to check out
+        if branch.get().symbolic_target().is_some() {
+            continue;
+        }
+
+        let is_head = head_name.is_some() && branch.get().name() == head_name.as_deref();
+        let upstream = branch
+            .upstream()
+            .ok()
+            .and_then(|u| u.name().ok().flatten().map(|n| n.to_string()));
+
+        branches.push(BranchInfo {
+            name,
+            is_remote: branch_type == git2::BranchType::Remote,
+            is_head,
+            upstream,
+        });
+    }
+
+    Ok(branches)
+}
+
+/// Creates a local branch at `start_point` (any revspec, defaults to HEAD) without
+/// checking it out. Returns the new branch's target commit.
+#[tauri::command]
+pub fn git_create_branch(repo_path: String, name: String, start_point: Option<String>) -> Result<String, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    let target = repo
+        .revparse_single(start_point.as_deref().unwrap_or("HEAD"))
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(|e| e.to_string())?;
+
+    let branch = repo.branch(&name, &target, false).map_err(|e| e.to_string())?;
+    let oid = branch.get().target().ok_or("Branch has no target")?;
+    Ok(oid.to_string())
+}
+
+/// Deletes a local branch. Unless `force` is set, refuses branches whose commits
+/// are not reachable from HEAD or the branch's upstream, like `git branch -d`.
+#[tauri::command]
+pub fn git_delete_branch(repo_path: String, name: String, force: Option<bool>) -> Result<(), String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let mut branch = repo
+        .find_branch(&name, git2::BranchType::Local)
+        .map_err(|e| e.to_string())?;
+
+    if branch.is_head() {
+        return Err(format!("Cannot delete branch '{}': it is checked out", name));
+    }
+
+    if !force.unwrap_or(false) {
+        let tip = branch.get().target().ok_or("Branch has no target")?;
+        let merged_into = |oid: Option<git2::Oid>| {
+            oid.is_some_and(|o| o == tip || repo.graph_descendant_of(o, tip).unwrap_or(false))
+        };
+
+        let head = repo.head().ok().and_then(|h| h.target());
+        let upstream = branch.upstream().ok().and_then(|u| u.get().target());
+        if !merged_into(head) && !merged_into(upstream) {
+            return Err(format!(
+                "Branch '{}' is not fully merged. Delete it with force to discard its commits.",
+                name
+            ));
+        }
+    }
+
+    branch.delete().map_err(|e| e.to_string())
+}
+
+#[derive(Serialize, Clone)]
+pub struct LineDiff {
+    pub line: u32,
+    pub diff_type: String, // "added", "modified", "deleted"
+}
+
+#[tauri::command]
+pub fn git_get_line_diff(repo_path: String, file_path: String) -> Result<Vec<LineDiff>, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    // Absolute to relative
+    let workdir = repo.workdir().ok_or("Not a working directory")?;
+    let abs_path = std::path::Path::new(&file_path);
+    let rel_path = abs_path.strip_prefix(workdir).map_err(|e| e.to_string())?;
+    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
+
+    let mut opts = DiffOptions::new();
+    opts.pathspec(&rel_path_str);
+    opts.context_lines(0); // We only want changed lines
+
+    let index = repo.index().map_err(|e| e.to_string())?;
+    let diff = repo
+        .diff_index_to_workdir(Some(&index), Some(&mut opts))
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+
+    diff.foreach(
+        &mut |_delta, _hunk| true,
         None,
         None,
         Some(&mut |_delta, _hunk, line| {
@@ -383,32 +1413,37 @@ pub fn git_churn(repo_path: String, days_limit: u32) -> Result<Vec<FileChurn>, S
             break;
         }
 
-        if let Ok(parent) = commit.parent(0) {
-            let tree = commit.tree().map_err(|e| e.to_string())?;
-            let parent_tree = parent.tree().map_err(|e| e.to_string())?;
+        // The root commit has no parent; diff it against the empty tree so files
+        // added in the very first commit are counted too
+        let parent_tree = if commit.parent_count() > 0 {
+            let parent = commit.parent(0).map_err(|e| e.to_string())?;
+            Some(parent.tree().map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+        let tree = commit.tree().map_err(|e| e.to_string())?;
 
-            let diff = repo
-                .diff_tree_to_tree(Some(&parent_tree), Some(&tree), None)
-                .map_err(|e| e.to_string())?;
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+            .map_err(|e| e.to_string())?;
 
-            diff.foreach(
-                &mut |delta, _hunk| {
-                    if let Some(path) = delta.new_file().path() {
-                        let path_str = path.to_string_lossy().to_string();
-                        let entry = churn_map.entry(path_str).or_insert((0, 0));
-                        entry.0 += 1;
-                        if commit_time > entry.1 {
-                            entry.1 = commit_time;
-                        }
+        diff.foreach(
+            &mut |delta, _hunk| {
+                if let Some(path) = delta.new_file().path() {
+                    let path_str = path.to_string_lossy().to_string();
+                    let entry = churn_map.entry(path_str).or_insert((0, 0));
+                    entry.0 += 1;
+                    if commit_time > entry.1 {
+                        entry.1 = commit_time;
                     }
-                    true
-                },
-                None,
-                None,
-                None,
-            )
-            .map_err(|e| e.to_string())?;
-        }
+                }
+                true
+            },
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| e.to_string())?;
     }
 
     let results = churn_map
@@ -422,9 +1457,161 @@ pub fn git_churn(repo_path: String, days_limit: u32) -> Result<Vec<FileChurn>, S
 
     Ok(results)
 }
+#[derive(Serialize, Clone)]
+struct CloneProgressEvent {
+    clone_id: Option<String>,
+    phase: &'static str, // "transfer" | "checkout"
+    received_objects: usize,
+    indexed_objects: usize,
+    total_objects: usize,
+    received_bytes: usize,
+    checkout_current: usize,
+    checkout_total: usize,
+}
+
+pub struct GitCloneState {
+    pub cancel_flags: Arc<Mutex<std::collections::HashMap<String, Arc<AtomicBool>>>>,
+}
+
+// transfer_progress fires for every object; cap the event rate the webview sees
+const CLONE_PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// Clones `url` into `path`. `depth` makes a shallow clone and `branch` checks out
+/// (and fetches only) that branch instead of the remote's default. Progress is
+/// emitted as `git-clone-progress`, tagged with `clone_id`, which can also be passed
+/// to `git_cancel_clone`. Returns the clone path.
+#[tauri::command(async)]
+pub fn git_clone<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, GitCloneState>,
+    url: String,
+    path: String,
+    depth: Option<i32>,
+    branch: Option<String>,
+    clone_id: Option<String>,
+) -> Result<String, String> {
+    let config = git2::Config::open_default().map_err(|e| e.to_string())?;
+    let mut callbacks = credential_callbacks(config);
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    if let Some(ref id) = clone_id {
+        let mut flags = state.cancel_flags.lock().unwrap();
+        if flags.contains_key(id) {
+            return Err(format!("Clone {} already running", id));
+        }
+        flags.insert(id.clone(), cancelled.clone());
+    }
+    let target = std::path::Path::new(&path);
+    let target_existed = target.exists();
+
+    let transfer_cancelled = cancelled.clone();
+    let transfer_app = app.clone();
+    let transfer_id = clone_id.clone();
+    let mut last_emit: Option<std::time::Instant> = None;
+    callbacks.transfer_progress(move |stats| {
+        let done = stats.indexed_objects() == stats.total_objects();
+        if done || last_emit.is_none_or(|t| t.elapsed() >= CLONE_PROGRESS_INTERVAL) {
+            last_emit = Some(std::time::Instant::now());
+            let _ = transfer_app.emit(
+                "git-clone-progress",
+                CloneProgressEvent {
+                    clone_id: transfer_id.clone(),
+                    phase: "transfer",
+                    received_objects: stats.received_objects(),
+                    indexed_objects: stats.indexed_objects(),
+                    total_objects: stats.total_objects(),
+                    received_bytes: stats.received_bytes(),
+                    checkout_current: 0,
+                    checkout_total: 0,
+                },
+            );
+        }
+        // Returning false aborts the transfer with a user error
+        !transfer_cancelled.load(Ordering::Relaxed)
+    });
+
+    let mut fetch_opts = git2::FetchOptions::new();
+    fetch_opts.remote_callbacks(callbacks);
+    if let Some(depth) = depth.filter(|d| *d > 0) {
+        fetch_opts.depth(depth);
+    }
+
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    let checkout_id = clone_id.clone();
+    let mut last_checkout_emit: Option<std::time::Instant> = None;
+    checkout.progress(move |_path, current, total| {
+        if current == total || last_checkout_emit.is_none_or(|t| t.elapsed() >= CLONE_PROGRESS_INTERVAL) {
+            last_checkout_emit = Some(std::time::Instant::now());
+            let _ = app.emit(
+                "git-clone-progress",
+                CloneProgressEvent {
+                    clone_id: checkout_id.clone(),
+                    phase: "checkout",
+                    received_objects: 0,
+                    indexed_objects: 0,
+                    total_objects: 0,
+                    received_bytes: 0,
+                    checkout_current: current,
+                    checkout_total: total,
+                },
+            );
+        }
+    });
+
+    let mut builder = git2::build::RepoBuilder::new();
+    builder.fetch_options(fetch_opts);
+    builder.with_checkout(checkout);
+
+    if let Some(ref branch) = branch {
+        builder.branch(branch);
+        // Single-branch: only map the requested branch into the remote-tracking namespace
+        let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch);
+        builder.remote_create(move |repo, name, url| {
+            repo.remote_with_fetch(name, url, &refspec)
+        });
+    }
+
+    let result = builder.clone(&url, target);
+
+    if let Some(ref id) = clone_id {
+        state.cancel_flags.lock().unwrap().remove(id);
+    }
+
+    match result {
+        Ok(_) => Ok(path),
+        Err(_) if cancelled.load(Ordering::Relaxed) => {
+            remove_partial_clone(target, target_existed);
+            Err("Clone cancelled".to_string())
+        }
+        Err(e) => Err(network_error(e)),
+    }
+}
+
+// git2 refuses to clone into a non-empty directory, so everything inside is ours
+fn remove_partial_clone(target: &std::path::Path, keep_dir: bool) {
+    if !keep_dir {
+        let _ = std::fs::remove_dir_all(target);
+        return;
+    }
+    if let Ok(entries) = std::fs::read_dir(target) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                let _ = std::fs::remove_dir_all(path);
+            } else {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+}
+
 #[tauri::command]
-pub fn git_clone(url: String, path: String) -> Result<(), String> {
-    git2::Repository::clone(&url, &path).map_err(|e| e.to_string())?;
+pub fn git_cancel_clone(state: tauri::State<'_, GitCloneState>, clone_id: String) -> Result<(), String> {
+    let flags = state.cancel_flags.lock().unwrap();
+    let flag = flags
+        .get(&clone_id)
+        .ok_or(format!("Clone {} not found", clone_id))?;
+    flag.store(true, Ordering::Relaxed);
     Ok(())
 }
 
@@ -436,7 +1623,6 @@ pub fn git_get_commit_details(repo_path: String, hash: String) -> Result<CommitD
 
     let author = commit.author();
     let date = commit.time();
-    let date_str = format!("{}", date.seconds());
 
     let mut files_changed = 0;
     let mut insertions = 0;
@@ -468,11 +1654,18 @@ pub fn git_get_commit_details(repo_path: String, hash: String) -> Result<CommitD
         deletions = stats.deletions();
     }
 
+    let committer = commit.committer();
+
     Ok(CommitDetails {
         hash: commit.id().to_string(),
         message: commit.message().unwrap_or("").to_string(),
         author: author.name().unwrap_or("Unknown").to_string(),
-        date: date_str,
+        author_email: author.email().unwrap_or("").to_string(),
+        committer: committer.name().unwrap_or("Unknown").to_string(),
+        committer_email: committer.email().unwrap_or("").to_string(),
+        date: format_git_time(date),
+        timestamp: date.seconds(),
+        offset_minutes: date.offset_minutes(),
         files_changed,
         insertions,
         deletions,
@@ -481,40 +1674,90 @@ pub fn git_get_commit_details(repo_path: String, hash: String) -> Result<CommitD
 #[derive(Serialize, Clone)]
 pub struct BlameEntry {
     pub author: String,
-    pub date: String,
+    pub date: String, // RFC3339 with the committer's offset
+    pub timestamp: i64,
+    pub offset_minutes: i32,
     pub hash: String,
 }
 
+// Workdir-relative path for blame. Falls back to a case-insensitive prefix match
+// for Windows paths whose drive letter or separators differ from the workdir's.
+fn blame_rel_path(workdir: &std::path::Path, file_path: &str) -> Result<std::path::PathBuf, String> {
+    let abs_path = std::path::Path::new(file_path);
+
+    // Simple path handling for Windows/Unix
+    if !abs_path.is_absolute() {
+        return Ok(abs_path.to_path_buf());
+    }
+    match abs_path.strip_prefix(workdir) {
+        Ok(p) => Ok(p.to_path_buf()),
+        Err(_) => {
+            let abs_str = abs_path.to_string_lossy().replace("\\", "/").to_lowercase();
+            let work_str = workdir.to_string_lossy().replace("\\", "/").to_lowercase();
+            if abs_str.starts_with(&work_str) {
+                let suffix = &abs_path.to_string_lossy()[workdir.to_string_lossy().len()..];
+                Ok(std::path::PathBuf::from(suffix.trim_start_matches(['\\', '/'])))
+            } else {
+                Err(format!("Path mismatch: file {:?} is not in workdir {:?}", abs_path, workdir))
+            }
+        }
+    }
+}
+
+// Noise filters for blame, shared by every blame command
+#[derive(Deserialize, Default, Clone)]
+pub struct BlameSettings {
+    pub ignore_whitespace: Option<bool>,
+    pub first_parent: Option<bool>, // skip commits brought in by merges
+    pub detect_copies: Option<u8>,  // like repeating -C: 1 moves in a commit, 2 copies in a commit, 3 any commit
+}
+
+impl BlameSettings {
+    fn to_options(&self) -> git2::BlameOptions {
+        let mut opts = git2::BlameOptions::new();
+        opts.ignore_whitespace(self.ignore_whitespace.unwrap_or(false))
+            .first_parent(self.first_parent.unwrap_or(false));
+        let copies = self.detect_copies.unwrap_or(0);
+        if copies >= 1 {
+            opts.track_copies_same_commit_moves(true);
+        }
+        if copies >= 2 {
+            opts.track_copies_same_commit_copies(true);
+        }
+        if copies >= 3 {
+            opts.track_copies_any_commit_copies(true);
+        }
+        opts
+    }
+
+    // Distinguishes cache entries computed with different settings
+    fn cache_key(&self) -> String {
+        format!(
+            "w{}p{}c{}",
+            self.ignore_whitespace.unwrap_or(false) as u8,
+            self.first_parent.unwrap_or(false) as u8,
+            self.detect_copies.unwrap_or(0)
+        )
+    }
+}
+
 #[tauri::command]
-pub fn git_blame(repo_path: String, file_path: String, line: u32) -> Result<BlameEntry, String> {
+pub fn git_blame(
+    repo_path: String,
+    file_path: String,
+    line: u32,
+    options: Option<BlameSettings>,
+) -> Result<BlameEntry, String> {
     let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
     
     // Absolute to relative
     let workdir = repo.workdir().ok_or("Not a working directory")?;
-    let abs_path = std::path::Path::new(&file_path);
-    
-    // Simple path handling for Windows/Unix
-    let rel_path_buf;
-    let rel_path = if abs_path.is_absolute() {
-        match abs_path.strip_prefix(workdir) {
-            Ok(p) => p,
-            Err(_) => {
-                let abs_str = abs_path.to_string_lossy().replace("\\", "/").to_lowercase();
-                let work_str = workdir.to_string_lossy().replace("\\", "/").to_lowercase();
-                if abs_str.starts_with(&work_str) {
-                    let suffix = &abs_path.to_string_lossy()[workdir.to_string_lossy().len()..];
-                    rel_path_buf = std::path::PathBuf::from(suffix.trim_start_matches(|c| c == '\\' || c == '/'));
-                    &rel_path_buf
-                } else {
-                    return Err(format!("Path mismatch: file {:?} is not in workdir {:?}", abs_path, workdir));
-                }
-            }
-        }
-    } else {
-        abs_path
-    };
+    let rel_path = blame_rel_path(workdir, &file_path)?;
 
-    let blame = repo.blame_file(rel_path, None).map_err(|e| e.to_string())?;
+    let mut opts = options.unwrap_or_default().to_options();
+    let blame = repo
+        .blame_file(&rel_path, Some(&mut opts))
+        .map_err(|e| e.to_string())?;
     
     if let Some(hunk) = blame.get_line(line as usize) {
         let commit_id = hunk.final_commit_id();
@@ -525,10 +1768,918 @@ pub fn git_blame(repo_path: String, file_path: String, line: u32) -> Result<Blam
 
         Ok(BlameEntry {
             author: author.name().unwrap_or("Unknown").to_string(),
-            date: format!("{}", date.seconds()),
+            date: format_git_time(date),
+            timestamp: date.seconds(),
+            offset_minutes: date.offset_minutes(),
             hash: commit_id.to_string(),
         })
     } else {
         Err("Line not found in blame".to_string())
     }
 }
+
+#[derive(Serialize, Clone)]
+pub struct BlameLine {
+    pub line: u32,
+    pub author: String,
+    pub date: String, // RFC3339 with the committer's offset
+    pub timestamp: i64,
+    pub offset_minutes: i32,
+    pub hash: String,
+    pub orig_commit_summary: String,
+}
+
+/// Blame for every line of a file in one call, for rendering a full gutter.
+#[tauri::command]
+pub fn git_blame_file(
+    repo_path: String,
+    file_path: String,
+    options: Option<BlameSettings>,
+) -> Result<Vec<BlameLine>, String> {
+    use std::collections::hash_map::Entry;
+    use std::collections::HashMap;
+
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Not a working directory")?;
+    let rel_path = blame_rel_path(workdir, &file_path)?;
+
+    let mut opts = options.unwrap_or_default().to_options();
+    let blame = repo
+        .blame_file(&rel_path, Some(&mut opts))
+        .map_err(|e| e.to_string())?;
+
+    // Many hunks share a commit; look each one up once
+    let mut commits: HashMap<git2::Oid, (String, git2::Time)> = HashMap::new();
+    let mut summaries: HashMap<git2::Oid, String> = HashMap::new();
+    let mut results = Vec::new();
+
+    for hunk in blame.iter() {
+        let commit_id = hunk.final_commit_id();
+        if let Entry::Vacant(slot) = commits.entry(commit_id) {
+            let commit = repo.find_commit(commit_id).map_err(|e| e.to_string())?;
+            let author = commit.author().name().unwrap_or("Unknown").to_string();
+            slot.insert((author, commit.time()));
+        }
+        let orig_id = hunk.orig_commit_id();
+        summaries.entry(orig_id).or_insert_with(|| {
+            repo.find_commit(orig_id)
+                .ok()
+                .and_then(|c| c.summary().map(|s| s.to_string()))
+                .unwrap_or_default()
+        });
+
+        let (author, date) = &commits[&commit_id];
+        let summary = &summaries[&orig_id];
+        let start = hunk.final_start_line() as u32;
+        for offset in 0..hunk.lines_in_hunk() as u32 {
+            results.push(BlameLine {
+                line: start + offset,
+                author: author.clone(),
+                date: format_git_time(*date),
+                timestamp: date.seconds(),
+                offset_minutes: date.offset_minutes(),
+                hash: commit_id.to_string(),
+                orig_commit_summary: summary.clone(),
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+const BLAME_MARGIN: u32 = 50;
+const BLAME_CACHE_FILES: usize = 32; // least recently used files are dropped past this
+
+#[derive(Serialize, Clone)]
+pub struct BlameHunk {
+    pub start_line: u32,
+    pub lines: u32,
+    pub hash: String,
+    pub author: String,
+    pub date: String, // RFC3339 with the committer's offset
+    pub timestamp: i64,
+    pub offset_minutes: i32,
+}
+
+// Per-file blame computed so far at a given HEAD, extended as the user scrolls
+pub struct CachedBlame {
+    head: git2::Oid,
+    covered: Vec<(u32, u32)>, // inclusive line ranges already blamed, sorted and merged
+    hunks: Vec<BlameHunk>,
+    last_used: std::time::Instant,
+}
+
+pub struct BlameCacheState {
+    pub files: Arc<Mutex<std::collections::HashMap<String, CachedBlame>>>,
+}
+
+// Parts of [lo, hi] not already in `covered`
+fn uncovered(covered: &[(u32, u32)], lo: u32, hi: u32) -> Vec<(u32, u32)> {
+    let mut missing = Vec::new();
+    let mut next = lo;
+    for &(a, b) in covered {
+        if b < next {
+            continue;
+        }
+        if a > hi {
+            break;
+        }
+        if a > next {
+            missing.push((next, a - 1));
+        }
+        next = b + 1;
+        if next > hi {
+            return missing;
+        }
+    }
+    missing.push((next, hi));
+    missing
+}
+
+fn merge_ranges(ranges: &mut Vec<(u32, u32)>) {
+    ranges.sort();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for &(a, b) in ranges.iter() {
+        match merged.last_mut() {
+            Some(last) if a <= last.1 + 1 => last.1 = last.1.max(b),
+            _ => merged.push((a, b)),
+        }
+    }
+    *ranges = merged;
+}
+
+/// Blame for just the visible range (plus a margin). Results are cached per file and
+/// HEAD, so scrolling only blames lines that haven't been computed yet.
+#[tauri::command]
+pub fn git_blame_incremental(
+    state: tauri::State<'_, BlameCacheState>,
+    repo_path: String,
+    file_path: String,
+    start_line: u32,
+    end_line: u32,
+    options: Option<BlameSettings>,
+) -> Result<Vec<BlameHunk>, String> {
+    let settings = options.unwrap_or_default();
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Not a working directory")?;
+    let rel_path = blame_rel_path(workdir, &file_path)?;
+
+    let head_commit = repo
+        .head()
+        .and_then(|h| h.peel_to_commit())
+        .map_err(|e| e.to_string())?;
+
+    // Clamp to the committed file's length; libgit2 rejects ranges past EOF
+    let blob = head_commit
+        .tree()
+        .and_then(|t| t.get_path(&rel_path))
+        .and_then(|entry| entry.to_object(&repo))
+        .and_then(|obj| obj.peel_to_blob())
+        .map_err(|e| e.to_string())?;
+    let content = blob.content();
+    let mut total_lines = content.iter().filter(|b| **b == b'\n').count() as u32;
+    if !content.is_empty() && !content.ends_with(b"\n") {
+        total_lines += 1;
+    }
+    if total_lines == 0 {
+        return Ok(vec![]);
+    }
+
+    let lo = start_line.saturating_sub(BLAME_MARGIN).max(1);
+    let hi = end_line.saturating_add(BLAME_MARGIN).min(total_lines);
+    if lo > hi {
+        return Ok(vec![]);
+    }
+
+    let cache_key = format!("{}|{}", file_path, settings.cache_key());
+    let head = head_commit.id();
+
+    // Work out what's missing under the lock but blame without it; a large file can take
+    // seconds, and other files' gutters shouldn't wait on that
+    let missing = {
+        let files = state.files.lock().unwrap();
+        match files.get(&cache_key) {
+            Some(cached) if cached.head == head => uncovered(&cached.covered, lo, hi),
+            _ => vec![(lo, hi)],
+        }
+    };
+
+    let mut blamed = Vec::new();
+    for (from, to) in missing {
+        let mut opts = settings.to_options();
+        opts.min_line(from as usize).max_line(to as usize);
+        let blame = repo
+            .blame_file(&rel_path, Some(&mut opts))
+            .map_err(|e| e.to_string())?;
+
+        let mut hunks = Vec::new();
+        for hunk in blame.iter() {
+            let commit_id = hunk.final_commit_id();
+            let commit = repo.find_commit(commit_id).map_err(|e| e.to_string())?;
+            let date = commit.time();
+            hunks.push(BlameHunk {
+                start_line: hunk.final_start_line() as u32,
+                lines: hunk.lines_in_hunk() as u32,
+                hash: commit_id.to_string(),
+                author: commit.author().name().unwrap_or("Unknown").to_string(),
+                date: format_git_time(date),
+                timestamp: date.seconds(),
+                offset_minutes: date.offset_minutes(),
+            });
+        }
+        blamed.push((from, to, hunks));
+    }
+
+    let mut files = state.files.lock().unwrap();
+    let cached = files.entry(cache_key).or_insert_with(|| CachedBlame {
+        head,
+        covered: Vec::new(),
+        hunks: Vec::new(),
+        last_used: std::time::Instant::now(),
+    });
+    if cached.head != head {
+        // HEAD moved: everything we knew is stale
+        cached.head = head;
+        cached.covered.clear();
+        cached.hunks.clear();
+    }
+    cached.last_used = std::time::Instant::now();
+
+    for (from, to, hunks) in blamed {
+        // A concurrent call may have filled part of this range meanwhile; only add the rest
+        for (a, b) in uncovered(&cached.covered, from, to) {
+            for hunk in &hunks {
+                let first = hunk.start_line.max(a);
+                let last = (hunk.start_line + hunk.lines - 1).min(b);
+                if first <= last {
+                    cached.hunks.push(BlameHunk {
+                        start_line: first,
+                        lines: last - first + 1,
+                        ..hunk.clone()
+                    });
+                }
+            }
+        }
+        cached.covered.push((from, to));
+        merge_ranges(&mut cached.covered);
+    }
+
+    // Compact: join adjacent hunks from the same commit (split by range boundaries)
+    cached.hunks.sort_by_key(|h| h.start_line);
+    let mut compacted: Vec<BlameHunk> = Vec::with_capacity(cached.hunks.len());
+    for hunk in cached.hunks.drain(..) {
+        match compacted.last_mut() {
+            Some(last) if last.hash == hunk.hash && last.start_line + last.lines == hunk.start_line => {
+                last.lines += hunk.lines;
+            }
+            _ => compacted.push(hunk),
+        }
+    }
+    cached.hunks = compacted;
+
+    let visible = cached
+        .hunks
+        .iter()
+        .filter(|h| h.start_line <= hi && h.start_line + h.lines > lo)
+        .cloned()
+        .collect();
+
+    if files.len() > BLAME_CACHE_FILES {
+        let oldest = files
+            .iter()
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(oldest) = oldest {
+            files.remove(&oldest);
+        }
+    }
+
+    Ok(visible)
+}
+
+#[derive(Serialize, Clone)]
+pub struct LinePermalink {
+    pub commit: String,
+    pub path: String, // repo-relative, forward slashes
+    pub line: u32,
+    pub remote_url: Option<String>, // https form of origin, if any
+}
+
+// Convert ssh/git remote URLs to their browsable https form. Local remotes (plain paths
+// and file:// URLs) have no web page, so they give None.
+fn normalize_remote_url(url: &str) -> Option<String> {
+    let url = url.trim();
+    let rest = if let Some(r) = url.strip_prefix("ssh://") {
+        // ssh://git@host:port/owner/repo
+        let r = r.split_once('@').map(|(_, h)| h).unwrap_or(r);
+        match r.split_once('/') {
+            Some((host, path)) => format!("{}/{}", host.split(':').next().unwrap_or(host), path),
+            None => r.to_string(),
+        }
+    } else if let Some(r) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("git://"))
+    {
+        // Drop any embedded credentials
+        match r.split_once('/') {
+            Some((authority, path)) => {
+                let host = authority.rsplit('@').next().unwrap_or(authority);
+                format!("{}/{}", host, path)
+            }
+            None => r.to_string(),
+        }
+    } else if url.contains("://") {
+        // file:// and anything else we can't map to a web page
+        return None;
+    } else if let Some((user_host, path)) = url.split_once(':') {
+        // scp-like: git@host:owner/repo. Like git, a slash before the colon makes it a
+        // path, and so does a single letter (a Windows drive such as C:\repos\x).
+        if user_host.len() < 2 || user_host.contains(['/', '\\']) {
+            return None;
+        }
+        let host = user_host.rsplit('@').next().unwrap_or(user_host);
+        format!("{}/{}", host, path.trim_start_matches('/'))
+    } else {
+        return None; // a local path
+    };
+
+    let rest = rest.trim_end_matches('/');
+    let rest = rest.strip_suffix(".git").unwrap_or(rest);
+    Some(format!("https://{}", rest))
+}
+
+#[tauri::command]
+pub fn git_line_permalink(repo_path: String, file_path: String, line: u32) -> Result<LinePermalink, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    // Absolute to relative, seeing through symlinks; anything else would put a local
+    // path into the link
+    repo.workdir().ok_or("Not a working directory")?;
+    let rel_path = crate::workdir_relative(&repo, std::path::Path::new(&file_path))
+        .ok_or("File is not inside the repository")?;
+    let rel_path_str = rel_path.to_string_lossy().replace("\\", "/");
+
+    let head = repo.head().map_err(|e| e.to_string())?;
+    let head_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+
+    let remote_url = repo
+        .find_remote("origin")
+        .ok()
+        .and_then(|remote| remote.url().and_then(normalize_remote_url));
+
+    Ok(LinePermalink {
+        commit: head_commit.id().to_string(),
+        path: rel_path_str,
+        line,
+        remote_url,
+    })
+}
+
+/// Unified diff of uncommitted work. With `include_staged` the diff is HEAD -> working tree,
+/// otherwise only the unstaged (index -> working tree) changes. Untracked files are included.
+#[tauri::command]
+pub fn git_export_worktree_patch(
+    repo_path: String,
+    output_path: Option<String>,
+    include_staged: bool,
+) -> Result<String, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    let mut opts = DiffOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true)
+        .context_lines(3);
+
+    let diff = if include_staged {
+        // Unborn HEAD (no commits yet) diffs against the empty tree
+        let head_tree = match repo.head() {
+            Ok(head) => Some(head.peel_to_tree().map_err(|e| e.to_string())?),
+            Err(_) => None,
+        };
+        repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))
+            .map_err(|e| e.to_string())?
+    } else {
+        let index = repo.index().map_err(|e| e.to_string())?;
+        repo.diff_index_to_workdir(Some(&index), Some(&mut opts))
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut patch = String::new();
+    diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+        let origin = line.origin();
+        if matches!(origin, '+' | '-' | ' ') {
+            patch.push(origin);
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .map_err(|e| e.to_string())?;
+
+    if let Some(out) = output_path {
+        std::fs::write(&out, &patch).map_err(|e| e.to_string())?;
+    }
+
+    Ok(patch)
+}
+
+#[derive(Serialize, Clone)]
+pub struct CheckoutChange {
+    pub path: String,
+    pub status: String, // "added", "modified", "deleted"
+}
+
+/// Files that checking out `target_rev` would touch, computed without modifying anything.
+#[tauri::command]
+pub fn git_checkout_preview(repo_path: String, target_rev: String) -> Result<Vec<CheckoutChange>, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Not a working directory")?;
+
+    let head_tree = repo
+        .head()
+        .and_then(|h| h.peel_to_tree())
+        .map_err(|e| e.to_string())?;
+    let target_tree = repo
+        .revparse_single(&target_rev)
+        .and_then(|obj| obj.peel_to_tree())
+        .map_err(|e| e.to_string())?;
+
+    let diff = repo
+        .diff_tree_to_tree(Some(&head_tree), Some(&target_tree), None)
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for delta in diff.deltas() {
+        let (file, status) = match delta.status() {
+            git2::Delta::Added => (delta.new_file(), "added"),
+            git2::Delta::Deleted => (delta.old_file(), "deleted"),
+            _ => (delta.new_file(), "modified"),
+        };
+        if let Some(path) = file.path() {
+            results.push(CheckoutChange {
+                path: workdir.join(path).to_string_lossy().to_string(),
+                status: status.to_string(),
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+// Credential lookup for network operations: ssh-agent first, then the configured
+// git credential helper (osxkeychain, manager-core, libsecret, ...).
+fn credential_callbacks<'a>(config: git2::Config) -> RemoteCallbacks<'a> {
+    let mut callbacks = RemoteCallbacks::new();
+    let mut tried_agent = false;
+    let mut tried_helper = false;
+
+    callbacks.credentials(move |url, username_from_url, allowed| {
+        if allowed.contains(CredentialType::SSH_KEY) && !tried_agent {
+            tried_agent = true;
+            return Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"));
+        }
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) && !tried_helper {
+            tried_helper = true;
+            return Cred::credential_helper(&config, url, username_from_url);
+        }
+        if allowed.contains(CredentialType::DEFAULT) {
+            return Cred::default();
+        }
+        // Returning an error stops libgit2 from retrying the same credentials forever
+        Err(git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Net,
+            "no valid credentials available",
+        ))
+    });
+
+    callbacks
+}
+
+fn network_error(e: git2::Error) -> String {
+    match e.code() {
+        git2::ErrorCode::Auth => format!("authentication failed: {}", e.message()),
+        git2::ErrorCode::NotFastForward => format!("rejected (non-fast-forward): {}", e.message()),
+        _ => e.to_string(),
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct PushProgressEvent {
+    remote: String,
+    current: usize,
+    total: usize,
+    bytes: usize,
+}
+
+/// Pushes `refspec` (defaults to the current branch) and emits `git-push-progress` events.
+#[tauri::command(async)]
+pub fn git_push<R: Runtime>(
+    app: AppHandle<R>,
+    repo_path: String,
+    remote: String,
+    refspec: Option<String>,
+) -> Result<(), String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let mut git_remote = repo.find_remote(&remote).map_err(|e| e.to_string())?;
+
+    let refspec = match refspec {
+        Some(r) => r,
+        None => {
+            let head = repo.head().map_err(|e| e.to_string())?;
+            let name = head.name().ok_or("HEAD is not a valid reference")?;
+            if !head.is_branch() {
+                return Err("Cannot push a detached HEAD without a refspec".to_string());
+            }
+            format!("{}:{}", name, name)
+        }
+    };
+
+    let config = repo.config().map_err(|e| e.to_string())?;
+    let mut callbacks = credential_callbacks(config);
+
+    let remote_name = remote.clone();
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        let _ = app.emit(
+            "git-push-progress",
+            PushProgressEvent {
+                remote: remote_name.clone(),
+                current,
+                total,
+                bytes,
+            },
+        );
+    });
+
+    // The server reports per-ref rejections here rather than as an error from push()
+    let rejection: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let rejection_cb = rejection.clone();
+    callbacks.push_update_reference(move |refname, status| {
+        if let Some(msg) = status {
+            *rejection_cb.lock().unwrap() = Some(format!("{}: {}", refname, msg));
+        }
+        Ok(())
+    });
+
+    let mut opts = git2::PushOptions::new();
+    opts.remote_callbacks(callbacks);
+
+    git_remote
+        .push(&[refspec.as_str()], Some(&mut opts))
+        .map_err(network_error)?;
+
+    if let Some(msg) = rejection.lock().unwrap().take() {
+        return Err(format!("rejected by remote: {}", msg));
+    }
+    Ok(())
+}
+
+fn fetch_options<'a>(repo: &Repository) -> Result<git2::FetchOptions<'a>, String> {
+    let config = repo.config().map_err(|e| e.to_string())?;
+    let mut opts = git2::FetchOptions::new();
+    opts.remote_callbacks(credential_callbacks(config));
+    Ok(opts)
+}
+
+#[tauri::command(async)]
+pub fn git_fetch(repo_path: String, remote: String) -> Result<(), String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let mut git_remote = repo.find_remote(&remote).map_err(|e| e.to_string())?;
+    let mut opts = fetch_options(&repo)?;
+
+    // Empty refspecs means "use the remote's configured fetch refspecs"
+    git_remote
+        .fetch::<&str>(&[], Some(&mut opts), None)
+        .map_err(network_error)?;
+    Ok(())
+}
+
+#[derive(Serialize, Clone)]
+pub struct BranchUpdate {
+    pub name: String,
+    pub old_hash: String,
+    pub new_hash: String,
+    pub commits: usize, // commits reachable from new_hash but not old_hash
+    pub forced: bool,   // the old tip is no longer an ancestor
+}
+
+#[derive(Serialize, Clone)]
+pub struct FetchReport {
+    pub new_branches: Vec<String>,
+    pub deleted_branches: Vec<String>,
+    pub updated_branches: Vec<BranchUpdate>,
+}
+
+// Remote-tracking branch name -> tip, e.g. "origin/main"
+fn remote_tips(repo: &Repository, remote: &str) -> Result<std::collections::HashMap<String, git2::Oid>, String> {
+    let mut tips = std::collections::HashMap::new();
+    let glob = format!("refs/remotes/{}/*", remote);
+    for reference in repo.references_glob(&glob).map_err(|e| e.to_string())? {
+        let reference = reference.map_err(|e| e.to_string())?;
+        if reference.symbolic_target().is_some() {
+            continue; // origin/HEAD
+        }
+        if let (Some(name), Some(oid)) = (reference.shorthand(), reference.target()) {
+            tips.insert(name.to_string(), oid);
+        }
+    }
+    Ok(tips)
+}
+
+/// Fetches `remote` and reports how its branches moved.
+#[tauri::command(async)]
+pub fn git_fetch_report(repo_path: String, remote: String, prune: Option<bool>) -> Result<FetchReport, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+    let before = remote_tips(&repo, &remote)?;
+
+    let mut git_remote = repo.find_remote(&remote).map_err(|e| e.to_string())?;
+    let mut opts = fetch_options(&repo)?;
+    if prune.unwrap_or(false) {
+        opts.prune(git2::FetchPrune::On);
+    }
+    git_remote
+        .fetch::<&str>(&[], Some(&mut opts), None)
+        .map_err(network_error)?;
+
+    let after = remote_tips(&repo, &remote)?;
+
+    let mut new_branches: Vec<String> = after
+        .keys()
+        .filter(|name| !before.contains_key(*name))
+        .cloned()
+        .collect();
+    let mut deleted_branches: Vec<String> = before
+        .keys()
+        .filter(|name| !after.contains_key(*name))
+        .cloned()
+        .collect();
+
+    let mut updated_branches = Vec::new();
+    for (name, new_oid) in &after {
+        let old_oid = match before.get(name) {
+            Some(old) if old != new_oid => *old,
+            _ => continue,
+        };
+        let (ahead, behind) = repo
+            .graph_ahead_behind(*new_oid, old_oid)
+            .map_err(|e| e.to_string())?;
+        updated_branches.push(BranchUpdate {
+            name: name.clone(),
+            old_hash: old_oid.to_string(),
+            new_hash: new_oid.to_string(),
+            commits: ahead,
+            forced: behind > 0,
+        });
+    }
+
+    new_branches.sort();
+    deleted_branches.sort();
+    updated_branches.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(FetchReport {
+        new_branches,
+        deleted_branches,
+        updated_branches,
+    })
+}
+
+/// Fetches `branch` (defaults to the current branch) and fast-forwards to it.
+/// Returns the number of commits pulled; errors if a real merge would be needed.
+/// A branch other than the checked-out one only has its ref moved; HEAD and the
+/// working tree are left alone.
+#[tauri::command(async)]
+pub fn git_pull(repo_path: String, remote: String, branch: Option<String>) -> Result<usize, String> {
+    let repo = Repository::discover(&repo_path).map_err(|e| e.to_string())?;
+
+    // Read HEAD symbolically so an unborn branch still counts as checked out
+    let head_branch = repo
+        .find_reference("HEAD")
+        .ok()
+        .and_then(|head| head.symbolic_target().map(str::to_string))
+        .and_then(|target| target.strip_prefix("refs/heads/").map(str::to_string));
+
+    let branch = match branch {
+        Some(b) => b,
+        None => head_branch.clone().ok_or("Cannot pull into a detached HEAD")?,
+    };
+    let is_current = head_branch.as_deref() == Some(branch.as_str());
+
+    // An explicit refspec so the remote-tracking branch moves too, not just FETCH_HEAD;
+    // otherwise ahead/behind and git_branches keep showing the old upstream
+    let tracking = format!("refs/remotes/{}/{}", remote, branch);
+    let refspec = format!("+refs/heads/{}:{}", branch, tracking);
+    let mut git_remote = repo.find_remote(&remote).map_err(|e| e.to_string())?;
+    let mut opts = fetch_options(&repo)?;
+    git_remote
+        .fetch(&[refspec.as_str()], Some(&mut opts), None)
+        .map_err(network_error)?;
+
+    let fetched = repo
+        .refname_to_id(&tracking)
+        .map_err(|e| format!("{}: {}", tracking, e))?;
+
+    // Analyse against the branch being pulled into, not whatever HEAD happens to be
+    let refname = format!("refs/heads/{}", branch);
+    let tip = repo.refname_to_id(&refname).ok();
+    if let Some(tip) = tip {
+        if tip == fetched {
+            return Ok(0);
+        }
+        if !repo.graph_descendant_of(fetched, tip).map_err(|e| e.to_string())? {
+            return Err("non-fast-forward: a manual merge is required".to_string());
+        }
+    }
+
+    // Count the commits we're about to move past
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk.push(fetched).map_err(|e| e.to_string())?;
+    if let Some(tip) = tip {
+        revwalk.hide(tip).map_err(|e| e.to_string())?;
+    }
+    let pulled = revwalk.count();
+
+    // Update the working tree first so a conflicting local edit aborts before the ref moves
+    if is_current {
+        let target = repo.find_object(fetched, None).map_err(|e| e.to_string())?;
+        repo.checkout_tree(&target, Some(git2::build::CheckoutBuilder::new().safe()))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let msg = format!("pull: fast-forward to {}", fetched);
+    match repo.find_reference(&refname) {
+        Ok(mut reference) => {
+            reference
+                .set_target(fetched, &msg)
+                .map_err(|e| e.to_string())?;
+        }
+        Err(_) => {
+            repo.reference(&refname, fetched, true, &msg)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(pulled)
+}
+
+#[derive(Serialize, Clone)]
+pub struct RepoProblem {
+    pub check: String, // "open", "head", "index", "tree", "lock"
+    pub message: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct RepoHealth {
+    pub healthy: bool,
+    pub problems: Vec<RepoProblem>,
+}
+
+/// Cheap integrity checks to turn opaque libgit2 failures into a concrete diagnosis.
+#[tauri::command]
+pub fn git_verify_repo(repo_path: String) -> Result<RepoHealth, String> {
+    let mut problems = Vec::new();
+    let mut problem = |check: &str, message: String| {
+        problems.push(RepoProblem {
+            check: check.to_string(),
+            message,
+        })
+    };
+
+    let repo = match Repository::discover(&repo_path) {
+        Ok(r) => r,
+        Err(e) => {
+            problem("open", e.to_string());
+            return Ok(RepoHealth {
+                healthy: false,
+                problems,
+            });
+        }
+    };
+
+    if repo.path().join("index.lock").exists() {
+        problem(
+            "lock",
+            "index.lock exists; another git process may be running or crashed".to_string(),
+        );
+    }
+
+    if let Err(e) = repo.index() {
+        problem("index", format!("index is unreadable: {}", e));
+    }
+
+    match repo.head() {
+        // A repo with no commits yet is healthy
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => {}
+        Err(e) => problem("head", format!("HEAD does not resolve: {}", e)),
+        Ok(head) => match head.peel_to_commit() {
+            Err(e) => problem("head", format!("HEAD does not point to a commit: {}", e)),
+            Ok(commit) => match commit.tree() {
+                Err(e) => problem("tree", format!("HEAD tree is unreachable: {}", e)),
+                Ok(tree) => {
+                    // Check the top-level entries exist without walking the whole tree
+                    if let Ok(odb) = repo.odb() {
+                        for entry in tree.iter() {
+                            if !odb.exists(entry.id()) {
+                                problem(
+                                    "tree",
+                                    format!(
+                                        "missing object {} for {}",
+                                        entry.id(),
+                                        entry.name().unwrap_or("?")
+                                    ),
+                                );
+                            }
+                        }
+                    }
+                }
+            },
+        },
+    }
+
+    Ok(RepoHealth {
+        healthy: problems.is_empty(),
+        problems,
+    })
+}
+
+#[derive(Serialize, Clone)]
+struct GitRawOutputEvent {
+    run_id: Option<String>,
+    stream: &'static str, // "stdout" | "stderr"
+    data: String,
+}
+
+/// Escape hatch for operations without a native wrapper: runs the `git` CLI in the repo,
+/// streams its output as `git-raw-output` events and returns the exit code. Pagers,
+/// credential prompts and editors are disabled, so anything that would wait on the
+/// user fails instead of hanging.
+#[tauri::command(async)]
+pub fn git_raw<R: Runtime>(
+    app: AppHandle<R>,
+    repo_path: String,
+    args: Vec<String>,
+    run_id: Option<String>,
+) -> Result<i32, String> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    let mut cmd = Command::new("git");
+    cmd.args(&args)
+        .current_dir(&repo_path)
+        .env("GIT_PAGER", "cat")
+        .env("PAGER", "cat")
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_EDITOR", "true")
+        .env("GIT_SEQUENCE_EDITOR", "true")
+        .env("GIT_MERGE_AUTOEDIT", "no")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    // Keep the user's own ssh command, but never let ssh ask for a passphrase
+    if std::env::var_os("GIT_SSH_COMMAND").is_none() {
+        cmd.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to run git: {}", e))?;
+    let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
+    let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
+
+    let forward = |mut reader: Box<dyn Read + Send>, stream: &'static str| {
+        let app = app.clone();
+        let run_id = run_id.clone();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 4096];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        let _ = app.emit(
+                            "git-raw-output",
+                            GitRawOutputEvent {
+                                run_id: run_id.clone(),
+                                stream,
+                                data: String::from_utf8_lossy(&buf[..n]).to_string(),
+                            },
+                        );
+                    }
+                }
+            }
+        })
+    };
+    let out_thread = forward(Box::new(stdout), "stdout");
+    let err_thread = forward(Box::new(stderr), "stderr");
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    let _ = out_thread.join();
+    let _ = err_thread.join();
+
+    // No exit code means the process was killed by a signal
+    Ok(status.code().unwrap_or(-1))
+}
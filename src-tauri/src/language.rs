@@ -0,0 +1,144 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// Files identified by name alone, regardless of extension
+fn from_file_name(name: &str) -> Option<&'static str> {
+    let id = match name {
+        "Dockerfile" | "Containerfile" => "dockerfile",
+        "Makefile" | "makefile" | "GNUmakefile" => "makefile",
+        "CMakeLists.txt" => "cmake",
+        "Cargo.lock" | "Pipfile" => "toml",
+        "Gemfile" | "Rakefile" | "Vagrantfile" => "ruby",
+        "Jenkinsfile" => "groovy",
+        ".bashrc" | ".bash_profile" | ".zshrc" | ".profile" => "shellscript",
+        ".gitignore" | ".dockerignore" => "ignore",
+        _ => {
+            if name.starts_with("Dockerfile.") {
+                "dockerfile"
+            } else if name.starts_with(".env") {
+                "dotenv"
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(id)
+}
+
+fn from_extension(path: &Path, ext: &str) -> Option<&'static str> {
+    let id = match ext {
+        "rs" => "rust",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "typescriptreact",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "javascriptreact",
+        "py" | "pyi" => "python",
+        "go" => "go",
+        "c" => "c",
+        "h" => {
+            // A header next to C++ sources is almost certainly C++
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let dir = path.parent().unwrap_or(Path::new(""));
+            let is_cpp = ["cpp", "cc", "cxx", "hpp"]
+                .iter()
+                .any(|e| dir.join(format!("{}.{}", stem, e)).exists());
+            if is_cpp {
+                "cpp"
+            } else {
+                "c"
+            }
+        }
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "cs" => "csharp",
+        "java" => "java",
+        "kt" | "kts" => "kotlin",
+        "swift" => "swift",
+        "rb" => "ruby",
+        "php" => "php",
+        "lua" => "lua",
+        "pl" | "pm" => "perl",
+        "sh" | "bash" | "zsh" => "shellscript",
+        "ps1" => "powershell",
+        "json" => "json",
+        "jsonc" => "jsonc",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "sql" => "sql",
+        "xml" | "svg" => "xml",
+        "dockerfile" => "dockerfile",
+        _ => return None,
+    };
+    Some(id)
+}
+
+// `#!/usr/bin/env python3` -> "python"
+fn from_shebang(path: &Path) -> Option<&'static str> {
+    let file = fs::File::open(path).ok()?;
+    let mut first = String::new();
+    BufReader::new(file).read_line(&mut first).ok()?;
+    let line = first.strip_prefix("#!")?.trim();
+
+    let mut parts = line.split_whitespace();
+    let mut program = parts.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // Skip env flags like `-S`
+        program = parts.find(|p| !p.starts_with('-'))?;
+    }
+
+    let id = match program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.') {
+        "sh" | "bash" | "zsh" | "dash" | "ksh" => "shellscript",
+        "python" => "python",
+        "node" | "deno" | "bun" => "javascript",
+        "ruby" => "ruby",
+        "perl" => "perl",
+        "php" => "php",
+        "lua" => "lua",
+        "pwsh" => "powershell",
+        _ => return None,
+    };
+    Some(id)
+}
+
+/// Canonical LSP language id for a path, ignoring user overrides.
+pub fn detect(path: &Path) -> String {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+    if let Some(id) = from_file_name(name) {
+        return id.to_string();
+    }
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        if let Some(id) = from_extension(path, &ext.to_lowercase()) {
+            return id.to_string();
+        }
+    }
+    if let Some(id) = from_shebang(path) {
+        return id.to_string();
+    }
+    "plaintext".to_string()
+}
+
+// User overrides live under "languageOverrides" in settings.json, keyed by
+// exact file name ("Jenkinsfile") or extension (".h").
+fn override_for(handle: &tauri::AppHandle, path: &Path) -> Option<String> {
+    let raw = fs::read_to_string(crate::settings_path(handle).ok()?).ok()?;
+    let settings: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    let overrides = settings.get("languageOverrides")?.as_object()?;
+
+    let name = path.file_name()?.to_string_lossy().to_string();
+    if let Some(id) = overrides.get(&name).and_then(|v| v.as_str()) {
+        return Some(id.to_string());
+    }
+    let ext = format!(".{}", path.extension()?.to_string_lossy());
+    overrides.get(&ext).and_then(|v| v.as_str()).map(|s| s.to_string())
+}
+
+#[tauri::command]
+pub fn detect_language(handle: tauri::AppHandle, path: String) -> String {
+    let path = Path::new(&path);
+    override_for(&handle, path).unwrap_or_else(|| detect(path))
+}
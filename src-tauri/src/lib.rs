@@ -3,16 +3,32 @@ mod lsp;
 mod terminal;
 mod agent_browser;
 mod background_cmd;
+mod dap;
+mod file_watcher;
+mod file_stream;
+mod file_ops;
+mod language;
+mod recent;
+mod search;
+mod scratch;
 
 use lsp::LspState;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::Manager;
 use terminal::TerminalState;
 use background_cmd::ProcessState;
+use file_watcher::FileWatcherState;
+use file_stream::FileStreamState;
+use search::{ReplaceUndoState, SearchState};
+use git::{BlameCacheState, GitCloneState};
+use dap::DapState;
+use scratch::ScratchState;
+use agent_browser::AgentState;
 
 const IGNORED_DIRS: &[&str] = &[
     "node_modules",
@@ -40,6 +56,9 @@ pub struct FileEntry {
     pub name: String,
     pub path: String,
     pub is_dir: bool,
+    pub is_symlink: bool,
+    pub size: u64,             // 0 for directories
+    pub modified: Option<i64>, // epoch millis
 }
 
 #[tauri::command]
@@ -61,54 +80,386 @@ async fn open_browser_window(handle: tauri::AppHandle, target_url: String) -> Re
     Ok(())
 }
 
-#[tauri::command]
-fn log_telemetry_event(handle: tauri::AppHandle, event: String) -> Result<(), String> {
-    use std::io::Write;
+// Past this size the log is moved to `telemetry.jsonl.1` (replacing the previous one)
+// and a fresh file is started, so at most about twice this is kept on disk.
+const TELEMETRY_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+pub struct TelemetryState {
+    pub enabled: Arc<AtomicBool>, // mirrors the user's opt-out; logging is a no-op while false
+}
+
+// Telemetry is on unless settings.json has `"telemetry": false`, so an opt-out is honoured
+// from startup rather than only once the frontend reports the preference.
+fn seed_telemetry_enabled(handle: &tauri::AppHandle, state: &TelemetryState) {
+    let enabled = settings_path(handle)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .and_then(|settings| settings["telemetry"].as_bool())
+        .unwrap_or(true);
+    state.enabled.store(enabled, Ordering::Relaxed);
+}
+
+fn telemetry_path(handle: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
     use tauri::path::BaseDirectory;
 
-    let log_path = handle
+    handle
         .path()
         .resolve("telemetry.jsonl", BaseDirectory::AppConfig)
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
+
+fn rotated_telemetry_path(log_path: &Path) -> PathBuf {
+    let mut rotated = log_path.as_os_str().to_owned();
+    rotated.push(".1");
+    PathBuf::from(rotated)
+}
+
+/// Appends one event to the telemetry log. `event` must be a JSON object; it is stamped
+/// with a server-side `ts` (epoch millis), replacing any the caller set.
+#[tauri::command]
+fn log_telemetry_event(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, TelemetryState>,
+    event: String,
+) -> Result<(), String> {
+    use std::io::Write;
+
+    if !state.enabled.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    // Re-serialized rather than written verbatim, so every line is exactly one object
+    let mut event: serde_json::Value =
+        serde_json::from_str(&event).map_err(|e| format!("Invalid telemetry event: {}", e))?;
+    let fields = event
+        .as_object_mut()
+        .ok_or("Invalid telemetry event: expected a JSON object")?;
+    fields.insert("ts".into(), chrono::Utc::now().timestamp_millis().into());
+    let line = serde_json::to_string(&event).map_err(|e| e.to_string())?;
+
+    let log_path = telemetry_path(&handle)?;
 
     if let Some(parent) = log_path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
 
+    if fs::metadata(&log_path).is_ok_and(|m| m.len() >= TELEMETRY_MAX_BYTES) {
+        fs::rename(&log_path, rotated_telemetry_path(&log_path)).map_err(|e| e.to_string())?;
+    }
+
     let mut file = fs::OpenOptions::new()
         .create(true)
         .append(true)
         .open(&log_path)
         .map_err(|e| e.to_string())?;
 
-    writeln!(file, "{}", event).map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Walks every logged event oldest first, the rotated file before the current one.
+// Lines that aren't valid JSON are passed as None; blank lines are skipped.
+fn each_telemetry_event(
+    handle: &tauri::AppHandle,
+    mut on_event: impl FnMut(Option<serde_json::Value>),
+) -> Result<(), String> {
+    use std::io::{BufRead, BufReader};
+
+    let log_path = telemetry_path(handle)?;
+    for path in [rotated_telemetry_path(&log_path), log_path] {
+        let file = match fs::File::open(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.to_string()),
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            on_event(serde_json::from_str(&line).ok());
+        }
+    }
     Ok(())
 }
 
+#[derive(Serialize)]
+struct TelemetryPage {
+    events: Vec<serde_json::Value>,
+    total: usize,     // events matching the filter
+    malformed: usize, // lines skipped because they weren't valid JSON
+}
+
 #[tauri::command]
-fn read_file(path: String) -> Result<String, String> {
-    let meta = fs::metadata(&path).map_err(|e| e.to_string())?;
+fn read_telemetry_events(
+    handle: tauri::AppHandle,
+    offset: usize,
+    limit: usize,
+    event_type_filter: Option<String>,
+) -> Result<TelemetryPage, String> {
+    let mut events = Vec::new();
+    let mut total = 0;
+    let mut malformed = 0;
+
+    each_telemetry_event(&handle, |event| {
+        let Some(event) = event else {
+            malformed += 1;
+            return;
+        };
+
+        if let Some(ref filter) = event_type_filter {
+            if event["type"].as_str() != Some(filter.as_str()) {
+                return;
+            }
+        }
+
+        if total >= offset && events.len() < limit {
+            events.push(event);
+        }
+        total += 1;
+    })?;
+
+    Ok(TelemetryPage { events, total, malformed })
+}
+
+/// The newest `limit` events, oldest first, reaching back into the rotated file when
+/// the current one holds fewer. Lines that aren't valid JSON are skipped.
+#[tauri::command]
+fn read_telemetry(handle: tauri::AppHandle, limit: usize) -> Result<Vec<serde_json::Value>, String> {
+    let mut recent = std::collections::VecDeque::with_capacity(limit);
+
+    each_telemetry_event(&handle, |event| {
+        let Some(event) = event else {
+            return;
+        };
+        if recent.len() == limit {
+            recent.pop_front();
+        }
+        if limit > 0 {
+            recent.push_back(event);
+        }
+    })?;
+
+    Ok(recent.into())
+}
+
+/// Deletes the telemetry log and its rotated predecessor.
+#[tauri::command]
+fn clear_telemetry(handle: tauri::AppHandle) -> Result<(), String> {
+    let log_path = telemetry_path(&handle)?;
+    for path in [rotated_telemetry_path(&log_path), log_path] {
+        match fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(())
+}
+
+/// Called by the frontend with the user's telemetry preference, at startup and whenever
+/// it changes.
+#[tauri::command]
+fn set_telemetry_enabled(state: tauri::State<'_, TelemetryState>, enabled: bool) {
+    state.enabled.store(enabled, Ordering::Relaxed);
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+#[derive(Serialize)]
+struct TextFile {
+    content: String,        // BOM stripped
+    encoding: &'static str, // "utf-8" | "utf-8-bom"
+    modified: Option<i64>,  // epoch millis; pass back to write_file as expected_mtime
+}
+
+fn mtime_millis(meta: &fs::Metadata) -> Option<i64> {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+}
+
+// Shared by the read commands. Errors with exactly "binary file" when the first 8KB
+// contain a NUL byte, so the frontend can switch to a "cannot display" view.
+fn read_text(path: &str) -> Result<TextFile, String> {
+    let meta = fs::metadata(path).map_err(|e| e.to_string())?;
     if meta.len() > 10 * 1024 * 1024 {
         return Err("File exceeds 10MB limit".into());
     }
-    fs::read_to_string(&path).map_err(|e| e.to_string())
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    if bytes[..bytes.len().min(8192)].contains(&0) {
+        return Err("binary file".into());
+    }
+
+    let (body, encoding) = match bytes.strip_prefix(UTF8_BOM) {
+        Some(rest) => (rest.to_vec(), "utf-8-bom"),
+        None => (bytes, "utf-8"),
+    };
+    let content = String::from_utf8(body).map_err(|e| e.to_string())?;
+    Ok(TextFile {
+        content,
+        encoding,
+        modified: mtime_millis(&meta),
+    })
+}
+
+#[tauri::command]
+fn read_file(path: String) -> Result<String, String> {
+    let file = read_text(&path)?;
+    // Keep the BOM here: callers of this command write the content back verbatim
+    if file.encoding == "utf-8-bom" {
+        return Ok(format!("\u{FEFF}{}", file.content));
+    }
+    Ok(file.content)
+}
+
+/// Like `read_file`, but strips a UTF-8 BOM and reports it in `encoding` so the editor
+/// can put it back on save.
+#[tauri::command]
+fn read_file_with_encoding(path: String) -> Result<TextFile, String> {
+    read_text(&path)
+}
+
+const MAX_RANGE_LEN: usize = 10 * 1024 * 1024;
+
+#[derive(Serialize)]
+struct FileRange {
+    content: String, // lossy: a multi-byte char cut at either edge shows up as U+FFFD
+    offset: u64,
+    bytes_read: usize, // advance the next offset by this, not by content.len()
+    total_size: u64,
+}
+
+/// Reads up to `length` bytes starting at `offset`, for windowed viewing of files too
+/// large for `read_file`. Works on files of any size; the window itself is capped at 10MB.
+#[tauri::command]
+fn read_file_range(path: String, offset: u64, length: usize) -> Result<FileRange, String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(&path).map_err(|e| e.to_string())?;
+    let total_size = file.metadata().map_err(|e| e.to_string())?.len();
+    let offset = offset.min(total_size);
+    file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+
+    let mut buf = Vec::with_capacity(length.min(MAX_RANGE_LEN));
+    file.take(length.min(MAX_RANGE_LEN) as u64)
+        .read_to_end(&mut buf)
+        .map_err(|e| e.to_string())?;
+
+    Ok(FileRange {
+        content: String::from_utf8_lossy(&buf).into_owned(),
+        offset,
+        bytes_read: buf.len(),
+        total_size,
+    })
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// Writes to a sibling temp file and renames it over `path`, so a crash or full disk
+// mid-write never leaves a truncated file. Keeps the existing file's permissions (and
+// owner, where allowed) and writes through symlinks rather than replacing them.
+fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let target = match fs::symlink_metadata(path) {
+        Ok(m) if m.file_type().is_symlink() => fs::canonicalize(path)?,
+        _ => path.to_path_buf(),
+    };
+    let existing = fs::metadata(&target).ok();
+    // rename() doesn't care about the target's mode, so refuse here the way a plain
+    // write would rather than silently replacing a file the user made read-only
+    if existing.as_ref().is_some_and(|meta| meta.permissions().readonly()) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is read-only", target.display()),
+        ));
+    }
+
+    let name = target.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let tmp = target.with_file_name(format!(".{}.{}.tmp", name, uuid::Uuid::new_v4().simple()));
+
+    let result = (|| {
+        {
+            use std::io::Write;
+            // Flush the data to disk before the rename makes it visible, so a crash
+            // can't leave the target pointing at an empty or partial file
+            let mut file = fs::File::create(&tmp)?;
+            file.write_all(bytes)?;
+            file.sync_all()?;
+        }
+        if let Some(meta) = &existing {
+            fs::set_permissions(&tmp, meta.permissions())?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::MetadataExt;
+                // Only succeeds when we're privileged or already the owner; not worth failing the save over
+                let _ = std::os::unix::fs::chown(&tmp, Some(meta.uid()), Some(meta.gid()));
+            }
+        }
+        fs::rename(&tmp, &target)?;
+        // Persist the rename itself; directories can't be opened like this on Windows
+        #[cfg(unix)]
+        if let Some(dir) = target.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
 }
 
+/// Writes `content` to `path` and returns the new mtime (epoch millis). With
+/// `expected_mtime`, refuses with "conflict: file changed on disk" if the file was
+/// modified or removed since the editor loaded it.
 #[tauri::command]
-fn write_file(path: String, content: String) -> Result<(), String> {
+fn write_file(
+    path: String,
+    content: String,
+    verify: Option<bool>,
+    expected_mtime: Option<i64>,
+) -> Result<Option<i64>, String> {
+    if let Some(expected) = expected_mtime {
+        let current = fs::metadata(&path).ok().and_then(|m| mtime_millis(&m));
+        if current != Some(expected) {
+            return Err("conflict: file changed on disk".into());
+        }
+    }
+
     if let Some(parent) = Path::new(&path).parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
-    fs::write(&path, &content).map_err(|e| e.to_string())
+    write_atomic(Path::new(&path), content.as_bytes()).map_err(|e| e.to_string())?;
+
+    // Verified mode: read back and compare checksums, for unreliable storage. write_atomic
+    // has already synced the data and the directory entry.
+    if verify.unwrap_or(false) {
+        let written = fs::read(&path).map_err(|e| e.to_string())?;
+        if sha256_hex(&written) != sha256_hex(content.as_bytes()) {
+            return Err("verification failed: file on disk does not match written content".into());
+        }
+    }
+    Ok(fs::metadata(&path).ok().and_then(|m| mtime_millis(&m)))
 }
 
-#[tauri::command]
-fn list_dir(path: String) -> Result<Vec<FileEntry>, String> {
-    let dir = Path::new(&path);
-    if !dir.is_dir() {
-        return Err("Not a directory".into());
+// Path of `path` relative to the repo's workdir, for git2's ignore checks
+fn workdir_relative(repo: &git2::Repository, path: &Path) -> Option<PathBuf> {
+    let workdir = repo.workdir()?;
+    if let Ok(rel) = path.strip_prefix(workdir) {
+        return Some(rel.to_path_buf());
     }
+    let canonical = fs::canonicalize(path).ok()?;
+    canonical.strip_prefix(fs::canonicalize(workdir).ok()?).ok().map(Path::to_path_buf)
+}
 
+// One directory level, filtered and sorted the way the file tree shows it
+fn read_dir_entries(dir: &Path, show_hidden: bool, repo: Option<&git2::Repository>) -> Result<Vec<FileEntry>, String> {
     let mut entries: Vec<FileEntry> = Vec::new();
     let read_dir = fs::read_dir(dir).map_err(|e| e.to_string())?;
 
@@ -117,13 +468,20 @@ fn list_dir(path: String) -> Result<Vec<FileEntry>, String> {
             Ok(e) => e,
             Err(_) => continue,
         };
-        let metadata = match entry.metadata() {
+        // DirEntry::metadata doesn't follow links, so dangling ones are still listed
+        let link_meta = match entry.metadata() {
             Ok(m) => m,
             Err(_) => continue,
         };
+        let is_symlink = link_meta.file_type().is_symlink();
+        let metadata = if is_symlink {
+            fs::metadata(entry.path()).unwrap_or(link_meta)
+        } else {
+            link_meta
+        };
         let name = entry.file_name().to_string_lossy().to_string();
 
-        if name.starts_with('.') {
+        if !show_hidden && name.starts_with('.') {
             continue;
         }
 
@@ -135,10 +493,22 @@ fn list_dir(path: String) -> Result<Vec<FileEntry>, String> {
             continue;
         }
 
+        if let Some(repo) = repo {
+            let ignored = workdir_relative(repo, &entry.path())
+                .filter(|rel| !rel.as_os_str().is_empty())
+                .is_some_and(|rel| repo.is_path_ignored(&rel).unwrap_or(false));
+            if ignored {
+                continue;
+            }
+        }
+
         entries.push(FileEntry {
             name,
             path: entry.path().to_string_lossy().to_string(),
             is_dir: metadata.is_dir(),
+            is_symlink,
+            size: if metadata.is_dir() { 0 } else { metadata.len() },
+            modified: mtime_millis(&metadata),
         });
     }
 
@@ -151,96 +521,217 @@ fn list_dir(path: String) -> Result<Vec<FileEntry>, String> {
     Ok(entries)
 }
 
+fn gitignore_repo(dir: &Path, respect_gitignore: Option<bool>) -> Option<git2::Repository> {
+    if !respect_gitignore.unwrap_or(false) {
+        return None;
+    }
+    git2::Repository::discover(dir).ok().filter(|r| !r.is_bare())
+}
+
+/// Lists a directory, skipping dotfiles (unless `show_hidden`) and the builtin ignore
+/// lists. With `respect_gitignore`, entries the enclosing repo ignores are dropped too;
+/// outside a repo only the builtin lists apply.
 #[tauri::command]
-fn get_basename(path: String) -> String {
-    Path::new(&path)
-        .file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| path.clone())
+fn list_dir(path: String, show_hidden: Option<bool>, respect_gitignore: Option<bool>) -> Result<Vec<FileEntry>, String> {
+    let dir = Path::new(&path);
+    if !dir.is_dir() {
+        return Err("Not a directory".into());
+    }
+
+    let repo = gitignore_repo(dir, respect_gitignore);
+    read_dir_entries(dir, show_hidden.unwrap_or(false), repo.as_ref())
 }
 
-#[derive(Serialize, Clone)]
-pub struct SearchMatch {
-    pub path: String,
-    pub line_number: u64,
-    pub column: u64,
-    pub line_text: String,
-    pub match_text: String,
+const MAX_TREE_NODES: usize = 20_000;
+
+#[derive(Serialize)]
+struct TreeEntry {
+    #[serde(flatten)]
+    entry: FileEntry,
+    depth: usize, // 0 for direct children of the root
 }
 
-#[tauri::command]
-fn ripgrep_search(query: String, cwd: String, case_sensitive: bool, regex: bool, max_results: Option<u32>) -> Result<Vec<SearchMatch>, String> {
-    if query.is_empty() {
-        return Ok(vec![]);
-    }
+#[derive(Serialize)]
+struct DirTree {
+    entries: Vec<TreeEntry>, // pre-order: each directory is followed by its contents
+    truncated: bool,         // hit MAX_TREE_NODES
+}
 
-    let max = max_results.unwrap_or(500);
-    let mut cmd = std::process::Command::new("rg");
-    cmd.arg("--json")
-        .arg("--max-count").arg("100")  // max matches per file
-        .arg("--max-filesize").arg("1M");
-    
-    if !cwd.is_empty() {
-        cmd.current_dir(&cwd);
+/// Walks `path` with the same filtering as `list_dir`; `max_depth` 0 lists only direct
+/// children, None means unlimited. Primes the tree or a file picker in one call.
+/// Symlinked directories are listed but not descended into.
+#[tauri::command(async)]
+fn list_dir_recursive(
+    path: String,
+    max_depth: Option<usize>,
+    show_hidden: Option<bool>,
+    respect_gitignore: Option<bool>,
+) -> Result<DirTree, String> {
+    let root = Path::new(&path);
+    if !root.is_dir() {
+        return Err("Not a directory".into());
     }
+    let show_hidden = show_hidden.unwrap_or(false);
+    let repo = gitignore_repo(root, respect_gitignore);
 
-    if !case_sensitive {
-        cmd.arg("--ignore-case");
-    }
-    if !regex {
-        cmd.arg("--fixed-strings");
+    let mut tree = DirTree {
+        entries: Vec::new(),
+        truncated: false,
+    };
+    // Reversed so popping yields entries in listing order
+    let mut stack: Vec<(FileEntry, usize)> = read_dir_entries(root, show_hidden, repo.as_ref())?
+        .into_iter()
+        .rev()
+        .map(|e| (e, 0))
+        .collect();
+
+    while let Some((entry, depth)) = stack.pop() {
+        if tree.entries.len() >= MAX_TREE_NODES {
+            tree.truncated = true;
+            break;
+        }
+        let descend = entry.is_dir && !entry.is_symlink && max_depth.is_none_or(|max| depth < max);
+        if descend {
+            // Unreadable subdirectories are still listed, just without children
+            if let Ok(children) = read_dir_entries(Path::new(&entry.path), show_hidden, repo.as_ref()) {
+                stack.extend(children.into_iter().rev().map(|e| (e, depth + 1)));
+            }
+        }
+        tree.entries.push(TreeEntry { entry, depth });
     }
 
-    cmd.arg("--").arg(&query);
+    Ok(tree)
+}
+
+#[derive(Serialize, Clone)]
+struct IgnoreExplanation {
+    ignored: bool,
+    reason: String, // "hidden" | "builtin" | "gitignore" | "whitelisted" | "none"
+    pattern: Option<String>,
+    source: Option<String>, // file the pattern came from
+    line: Option<usize>,    // 1-based line of the pattern in `source`
+}
+
+impl IgnoreExplanation {
+    fn new(ignored: bool, reason: &str) -> Self {
+        IgnoreExplanation {
+            ignored,
+            reason: reason.to_string(),
+            pattern: None,
+            source: None,
+            line: None,
+        }
+    }
+}
 
-    let output = cmd.output().map_err(|e| format!("Failed to run rg: {}", e))?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
+// Best effort: globs only remember their text, so find it in the source file
+fn pattern_line(source: &Path, pattern: &str) -> Option<usize> {
+    let raw = fs::read_to_string(source).ok()?;
+    raw.lines()
+        .position(|l| l.trim_end() == pattern)
+        .map(|i| i + 1)
+}
 
-    let mut results: Vec<SearchMatch> = Vec::new();
+// Ignore files that apply to `path`, highest precedence first: nested .gitignore
+// files from the closest directory up to the repo root, then .git/info/exclude,
+// then the user's core.excludesFile.
+fn ignore_sources(path: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut sources = Vec::new();
+    let mut root = None;
 
-    for line in stdout.lines() {
-        if results.len() >= max as usize {
+    for dir in path.ancestors().skip(1) {
+        let file = dir.join(".gitignore");
+        if file.is_file() {
+            sources.push((dir.to_path_buf(), file));
+        }
+        if dir.join(".git").exists() {
+            root = Some(dir.to_path_buf());
             break;
         }
+    }
 
-        let parsed: serde_json::Value = match serde_json::from_str(line) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
+    let Some(root) = root else {
+        return sources;
+    };
 
-        if parsed["type"] != "match" {
-            continue;
+    let exclude = root.join(".git").join("info").join("exclude");
+    if exclude.is_file() {
+        sources.push((root.clone(), exclude));
+    }
+
+    let global = git2::Config::open_default()
+        .ok()
+        .and_then(|c| c.get_path("core.excludesfile").ok())
+        .or_else(|| {
+            let base = std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+            Some(base.join("git").join("ignore"))
+        });
+    if let Some(global) = global.filter(|g| g.is_file()) {
+        sources.push((root, global));
+    }
+
+    sources
+}
+
+/// Explains why `path` is (or isn't) hidden from the file tree.
+#[tauri::command]
+fn explain_ignore(path: String) -> Result<IgnoreExplanation, String> {
+    let path = Path::new(&path);
+    let is_dir = path.is_dir();
+
+    // The same filters list_dir applies before any ignore file is consulted. Only look
+    // below the repo root, so a workspace that itself lives in a dot directory is fine.
+    let relative = path
+        .ancestors()
+        .skip(1)
+        .find(|dir| dir.join(".git").exists())
+        .and_then(|root| path.strip_prefix(root).ok())
+        .unwrap_or_else(|| Path::new(path.file_name().unwrap_or_default()));
+    for component in relative.components().rev() {
+        let name = component.as_os_str().to_string_lossy();
+        if name.starts_with('.') && name != "." && name != ".." {
+            let mut exp = IgnoreExplanation::new(true, "hidden");
+            exp.pattern = Some(name.to_string());
+            return Ok(exp);
+        }
+        if IGNORED_DIRS.contains(&name.as_ref()) || IGNORED_FILES.contains(&name.as_ref()) {
+            let mut exp = IgnoreExplanation::new(true, "builtin");
+            exp.pattern = Some(name.to_string());
+            return Ok(exp);
         }
+    }
 
-        let data = &parsed["data"];
-        let path_text = data["path"]["text"].as_str().unwrap_or("");
-        let line_number = data["line_number"].as_u64().unwrap_or(0);
-        let line_text = data["lines"]["text"].as_str().unwrap_or("").trim_end().to_string();
-
-        // Get first submatch
-        if let Some(submatches) = data["submatches"].as_array() {
-            for sm in submatches {
-                let match_text = sm["match"]["text"].as_str().unwrap_or("").to_string();
-                let col = sm["start"].as_u64().unwrap_or(0);
-
-                let full_path = Path::new(&cwd).join(path_text).to_string_lossy().to_string();
-
-                results.push(SearchMatch {
-                    path: full_path,
-                    line_number,
-                    column: col,
-                    line_text: line_text.clone(),
-                    match_text,
-                });
-
-                if results.len() >= max as usize {
-                    break;
-                }
-            }
+    for (root, file) in ignore_sources(path) {
+        let mut builder = ignore::gitignore::GitignoreBuilder::new(&root);
+        if let Some(err) = builder.add(&file) {
+            return Err(format!("{}: {}", file.display(), err));
         }
+        let matcher = builder.build().map_err(|e| e.to_string())?;
+
+        let (ignored, glob) = match matcher.matched_path_or_any_parents(path, is_dir) {
+            ignore::Match::None => continue,
+            ignore::Match::Ignore(glob) => (true, glob),
+            ignore::Match::Whitelist(glob) => (false, glob),
+        };
+
+        let mut exp = IgnoreExplanation::new(ignored, if ignored { "gitignore" } else { "whitelisted" });
+        exp.pattern = Some(glob.original().to_string());
+        exp.line = pattern_line(&file, glob.original());
+        exp.source = Some(file.to_string_lossy().to_string());
+        return Ok(exp);
     }
 
-    Ok(results)
+    Ok(IgnoreExplanation::new(false, "none"))
+}
+
+#[tauri::command]
+fn get_basename(path: String) -> String {
+    Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone())
 }
 
 #[derive(Serialize)]
@@ -276,22 +767,58 @@ async fn run_shell_cmd(command: String, cwd: String) -> Result<ShellResult, Stri
     })
 }
 
+/// Replaces the first match of `search` in a file, or every match with `all`. `occurrence`
+/// (0-based) targets one specific match instead and takes precedence over `all`.
 #[tauri::command]
-fn search_replace(file_path: String, search: String, replace: String, all: bool) -> Result<u32, String> {
+fn search_replace(
+    file_path: String,
+    search: String,
+    replace: String,
+    all: bool,
+    regex: Option<bool>,
+    occurrence: Option<usize>,
+) -> Result<u32, String> {
     let content = fs::read_to_string(&file_path).map_err(|e| e.to_string())?;
 
-    let (new_content, count) = if all {
+    let (new_content, count) = if regex.unwrap_or(false) {
+        // `replace` may use $1 / ${name} to refer to capture groups
+        let re = regex::Regex::new(&search).map_err(|e| e.to_string())?;
+        if let Some(n) = occurrence {
+            match re.captures_iter(&content).nth(n) {
+                Some(caps) => {
+                    let m = caps.get(0).unwrap();
+                    let mut expanded = String::new();
+                    caps.expand(&replace, &mut expanded);
+                    (splice(&content, m.start(), m.end(), &expanded), 1)
+                }
+                None => (content, 0),
+            }
+        } else {
+            let count = if all {
+                re.find_iter(&content).count() as u32
+            } else {
+                re.is_match(&content) as u32
+            };
+            let new = if all {
+                re.replace_all(&content, replace.as_str())
+            } else {
+                re.replace(&content, replace.as_str())
+            };
+            (new.into_owned(), count)
+        }
+    } else if let Some(n) = occurrence {
+        // match_indices offsets always sit on char boundaries, unlike hand-rolled arithmetic
+        match content.match_indices(&search).nth(n) {
+            Some((pos, m)) => (splice(&content, pos, pos + m.len(), &replace), 1),
+            None => (content, 0),
+        }
+    } else if all {
         let count = content.matches(&search).count() as u32;
         (content.replace(&search, &replace), count)
     } else {
-        if let Some(pos) = content.find(&search) {
-            let mut new = String::with_capacity(content.len());
-            new.push_str(&content[..pos]);
-            new.push_str(&replace);
-            new.push_str(&content[pos + search.len()..]);
-            (new, 1)
-        } else {
-            (content, 0)
+        match content.match_indices(&search).next() {
+            Some((pos, m)) => (splice(&content, pos, pos + m.len(), &replace), 1),
+            None => (content, 0),
         }
     };
 
@@ -302,74 +829,342 @@ fn search_replace(file_path: String, search: String, replace: String, all: bool)
     Ok(count)
 }
 
-#[tauri::command]
-fn get_user_config_dir(handle: tauri::AppHandle) -> Result<String, String> {
+fn splice(content: &str, start: usize, end: usize, with: &str) -> String {
+    let mut new = String::with_capacity(content.len() - (end - start) + with.len());
+    new.push_str(&content[..start]);
+    new.push_str(with);
+    new.push_str(&content[end..]);
+    new
+}
+
+fn settings_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
     use tauri::path::BaseDirectory;
     handle
         .path()
         .resolve("settings.json", BaseDirectory::Config)
-        .map(|p: std::path::PathBuf| p.to_string_lossy().to_string())
         .map_err(|e: tauri::Error| e.to_string())
 }
 
+#[tauri::command]
+fn get_user_config_dir(handle: tauri::AppHandle) -> Result<String, String> {
+    settings_path(&handle).map(|p| p.to_string_lossy().to_string())
+}
+
+pub struct SettingsState {
+    pub lock: Arc<Mutex<()>>, // serializes read-modify-write across windows
+}
+
+// Reads settings.json, creating it as `{}` if it doesn't exist yet. A file that isn't
+// a JSON object is an error rather than something to overwrite.
+fn load_settings(path: &Path) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            write_atomic(path, b"{}").map_err(|e| e.to_string())?;
+            return Ok(serde_json::Map::new());
+        }
+        Err(e) => return Err(e.to_string()),
+    };
+    match serde_json::from_str(&text) {
+        Ok(serde_json::Value::Object(settings)) => Ok(settings),
+        Ok(_) => Err(format!("{} is not a JSON object", path.display())),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+// JSON Merge Patch (RFC 7396): objects merge key by key, `null` deletes a key, and
+// anything else replaces what was there.
+fn merge_settings(
+    target: &mut serde_json::Map<String, serde_json::Value>,
+    patch: serde_json::Map<String, serde_json::Value>,
+) {
+    for (key, value) in patch {
+        match value {
+            serde_json::Value::Null => {
+                target.remove(&key);
+            }
+            serde_json::Value::Object(patch) => {
+                if let Some(serde_json::Value::Object(existing)) = target.get_mut(&key) {
+                    merge_settings(existing, patch);
+                } else {
+                    let mut fresh = serde_json::Map::new();
+                    merge_settings(&mut fresh, patch);
+                    target.insert(key, fresh.into());
+                }
+            }
+            value => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
+#[tauri::command]
+fn read_settings(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, SettingsState>,
+) -> Result<serde_json::Value, String> {
+    let _guard = state.lock.lock().unwrap();
+    load_settings(&settings_path(&handle)?).map(serde_json::Value::Object)
+}
+
+/// Deep-merges `patch` into settings.json and returns the result. The file is replaced
+/// atomically, and concurrent writers in this process are serialized.
+#[tauri::command]
+fn write_settings(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, SettingsState>,
+    patch: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let serde_json::Value::Object(patch) = patch else {
+        return Err("Settings patch must be a JSON object".to_string());
+    };
+
+    let _guard = state.lock.lock().unwrap();
+    let path = settings_path(&handle)?;
+    let mut settings = load_settings(&path)?;
+    merge_settings(&mut settings, patch);
+
+    let settings = serde_json::Value::Object(settings);
+    let text = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+    write_atomic(&path, text.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(settings)
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_http::init())
         .manage(TerminalState {
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            buffers: Arc::new(Mutex::new(HashMap::new())),
         })
         .manage(LspState {
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            launches: Arc::new(Mutex::new(HashMap::new())),
         })
         .manage(ProcessState {
              processes: Arc::new(Mutex::new(HashMap::new())),
+             multiplex: Arc::new(AtomicBool::new(false)),
+        })
+        .manage(FileWatcherState {
+            watchers: Arc::new(Mutex::new(HashMap::new())),
+        })
+        .manage(FileStreamState {
+            streams: Arc::new(Mutex::new(HashMap::new())),
+        })
+        .manage(BlameCacheState {
+            files: Arc::new(Mutex::new(HashMap::new())),
+        })
+        .manage(GitCloneState {
+            cancel_flags: Arc::new(Mutex::new(HashMap::new())),
+        })
+        .manage(SearchState {
+            searches: Arc::new(Mutex::new(HashMap::new())),
+        })
+        .manage(ReplaceUndoState {
+            entries: Arc::new(Mutex::new(Vec::new())),
+        })
+        .manage(DapState {
+            sessions: Arc::new(Mutex::new(HashMap::new())),
+        })
+        .manage(ScratchState {
+            files: Arc::new(Mutex::new(Vec::new())),
+        })
+        .manage(SettingsState {
+            lock: Arc::new(Mutex::new(())),
+        })
+        .manage(TelemetryState {
+            enabled: Arc::new(AtomicBool::new(true)),
+        })
+        .manage(AgentState {
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        })
+        .setup(|app| {
+            scratch::sweep_expired(app.handle());
+            seed_telemetry_enabled(app.handle(), &app.state::<TelemetryState>());
+            Ok(())
         })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             read_file,
+            read_file_with_encoding,
+            read_file_range,
             write_file,
             list_dir,
+            list_dir_recursive,
+            file_ops::rename_path,
+            file_ops::delete_path,
+            file_ops::create_directory,
+            file_ops::path_exists,
+            file_ops::reveal_in_file_manager,
+            file_ops::open_with_default,
+            explain_ignore,
             get_basename,
             get_user_config_dir,
+            read_settings,
+            write_settings,
+            language::detect_language,
+            recent::add_recent_item,
+            recent::get_recent_items,
+            scratch::create_scratch_file,
+            scratch::list_scratch_files,
             terminal::spawn_terminal,
+            terminal::spawn_command_terminal,
+            terminal::kill_terminal,
+            terminal::read_terminal_buffer,
+            terminal::release_terminal_buffer,
             terminal::write_to_terminal,
             terminal::resize_terminal,
+            terminal::resize_terminals,
             log_telemetry_event,
+            read_telemetry_events,
+            read_telemetry,
+            clear_telemetry,
+            set_telemetry_enabled,
             git::git_status,
+            git::git_is_dirty,
+            git::git_path_tracking,
             git::git_diff,
+            git::git_diff_structured,
+            git::git_diff_unstaged_changes,
             git::git_log,
+            git::git_search_history,
             git::git_read_file,
+            git::git_read_blob_bytes,
             git::git_stage,
+            git::git_stage_hunk,
             git::git_unstage,
+            git::git_discard_changes,
             git::git_commit,
+            git::git_commit_amend,
+            git::validate_commit_message,
             git::git_get_branch,
+            git::git_branches,
+            git::git_create_branch,
+            git::git_delete_branch,
             git::git_get_line_diff,
             git::git_churn,
             git::git_clone,
+            git::git_cancel_clone,
+            git::git_push,
+            git::git_fetch,
+            git::git_fetch_report,
+            git::git_pull,
             git::git_get_commit_details,
             git::git_blame,
+            git::git_blame_file,
+            git::git_blame_incremental,
+            git::git_line_permalink,
+            git::git_export_worktree_patch,
+            git::git_checkout_preview,
+            git::git_verify_repo,
+            git::git_raw,
             lsp::lsp_start,
             lsp::lsp_send,
+            lsp::lsp_request,
             lsp::lsp_stop,
+            lsp::lsp_restart,
             lsp::lsp_list,
-            ripgrep_search,
+            lsp::lsp_warm,
+            lsp::lsp_reconcile,
+            lsp::lsp_get_progress,
+            search::ripgrep_search,
+            search::list_active_searches,
+            search::cancel_all_searches,
+            search::cancel_search,
+            search::find_files,
             run_shell_cmd,
             search_replace,
+            search::search_replace_project,
+            search::undo_replace,
             open_browser_window,
             agent_browser::agent_spawn,
+            agent_browser::agent_navigate,
+            agent_browser::agent_current_url,
             agent_browser::agent_execute,
             agent_browser::agent_click,
             agent_browser::agent_type,
             agent_browser::agent_get_content,
+            agent_browser::agent_result,
             agent_browser::agent_scroll,
             agent_browser::agent_hover,
             agent_browser::agent_close,
+            agent_browser::agent_screenshot,
             background_cmd::exec_background_cmd,
             background_cmd::check_background_cmd,
             background_cmd::kill_background_cmd,
+            background_cmd::list_background_cmds,
+            background_cmd::subscribe_all_cmd_output,
+            background_cmd::unsubscribe_all_cmd_output,
+            file_watcher::watch_file,
+            file_watcher::unwatch_file,
+            file_stream::stream_file,
+            file_stream::ack_file_chunk,
+            file_stream::cancel_stream,
+            dap::dap_connect,
+            dap::dap_spawn,
+            dap::dap_send,
+            dap::dap_disconnect,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                scratch::cleanup_session(handle);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs search_replace against a scratch file and returns (count, new content)
+    fn replace_in(content: &str, search: &str, replace: &str, regex: bool, occurrence: Option<usize>) -> (u32, String) {
+        let path = std::env::temp_dir().join(format!("ted-search-replace-{}.txt", uuid::Uuid::new_v4().simple()));
+        fs::write(&path, content).unwrap();
+        let count = search_replace(
+            path.to_string_lossy().into_owned(),
+            search.into(),
+            replace.into(),
+            false,
+            Some(regex),
+            occurrence,
+        )
+        .unwrap();
+        let result = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        (count, result)
+    }
+
+    #[test]
+    fn replaces_nth_literal_match() {
+        assert_eq!(replace_in("a1 a2 a3", "a", "b", false, Some(1)), (1, "a1 b2 a3".into()));
+        assert_eq!(replace_in("a1 a2 a3", "a", "b", false, Some(0)), (1, "b1 a2 a3".into()));
+    }
+
+    #[test]
+    fn out_of_range_occurrence_leaves_file_alone() {
+        assert_eq!(replace_in("a1 a2", "a", "b", false, Some(2)), (0, "a1 a2".into()));
+        assert_eq!(replace_in("a1 a2", "a(\\d)", "b", true, Some(5)), (0, "a1 a2".into()));
+    }
+
+    #[test]
+    fn regex_occurrence_expands_captures() {
+        assert_eq!(
+            replace_in("x=1; y=2; z=3", "(\\w)=(\\d)", "$2=$1", true, Some(1)),
+            (1, "x=1; 2=y; z=3".into())
+        );
+    }
+
+    #[test]
+    fn multibyte_content_around_match() {
+        assert_eq!(replace_in("日本語 foo 🎉 foo 語", "foo", "bär", false, Some(1)), (1, "日本語 foo 🎉 bär 語".into()));
+        assert_eq!(replace_in("🎉日本🎉日本", "(日)本", "${1}月", true, Some(1)), (1, "🎉日本🎉日月".into()));
+    }
 }
@@ -2,17 +2,71 @@ use serde::Serialize;
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Read, Write};
 use std::process::{Child, Command, Stdio};
-use std::sync::{Arc, Mutex};
-use std::thread;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, Runtime};
 
 pub struct LspSession {
-    pub child: Child,
+    pub child: Arc<Mutex<Child>>, // shared with the exit watcher
     pub stdin: Arc<Mutex<Box<dyn Write + Send>>>,
+    pub open_documents: Arc<Mutex<HashMap<String, bool>>>, // uri -> opened by lsp_warm
+    pub progress: Option<Arc<Mutex<HashMap<String, LspProgress>>>>, // token -> state, when tracked
+    pub readers: Vec<JoinHandle<()>>, // stdout, stderr lines, stderr events
+    pub stopped: Arc<AtomicBool>,     // readers stop emitting once set
+    pub pending: PendingRequests,     // lsp_request calls awaiting a response
+}
+
+type PendingRequests = Arc<Mutex<HashMap<String, tokio::sync::oneshot::Sender<serde_json::Value>>>>;
+
+// Ids for lsp_request are strings with this prefix, so they can't collide with the
+// numeric ids the frontend assigns to the requests it sends itself
+const REQUEST_ID_PREFIX: &str = "ted-";
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+const EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+const EXIT_FALLBACK_INTERVAL: Duration = Duration::from_secs(2);
+
+const READER_JOIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Kills the server and waits for its reader threads, so a quick restart under the same
+// id never has the old readers emitting alongside the new ones. Killing closes the
+// pipes, which ends the readers; if a grandchild keeps them open we give up after the
+// timeout, and the `stopped` flag keeps the stragglers quiet.
+fn stop_session(session: LspSession) {
+    session.stopped.store(true, Ordering::SeqCst);
+    // Dropping the senders fails any lsp_request still waiting on this server
+    session.pending.lock().unwrap().clear();
+    let mut child = session.child.lock().unwrap();
+    let _ = child.kill();
+    let _ = child.wait();
+    drop(child);
+
+    let deadline = Instant::now() + READER_JOIN_TIMEOUT;
+    for reader in session.readers {
+        while !reader.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        if reader.is_finished() {
+            let _ = reader.join();
+        }
+    }
 }
 
 pub struct LspState {
     pub sessions: Arc<Mutex<HashMap<String, LspSession>>>,
+    // Every server ever started, kept after it exits so lsp_restart can revive a crashed one
+    pub launches: Arc<Mutex<HashMap<String, LspLaunch>>>,
+}
+
+#[derive(Clone)]
+pub struct LspLaunch {
+    pub command: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub track_progress: bool,
 }
 
 #[derive(Serialize, Clone)]
@@ -24,7 +78,157 @@ struct LspMessageEvent {
 #[derive(Serialize, Clone)]
 struct LspErrorEvent {
     server_id: String,
-    error: String,
+    error: String,       // may span several lines when a traceback was coalesced
+    level: &'static str, // "info" | "warn" | "error"
+}
+
+// A burst of stderr lines arriving closer together than this can be merged into one event
+const STDERR_COALESCE_WINDOW: Duration = Duration::from_millis(100);
+const MAX_STDERR_BLOCK_LINES: usize = 200;
+
+// Stack frames and trailers that belong to the line before them
+const CONTINUATION_PREFIXES: &[&str] = &["stack backtrace:", "note:", "Caused by:", "Stack trace:", "at "];
+
+// Level from the line's leading level token ("ERROR", "[warn]", "error[E0308]:", ...),
+// skipping a timestamp before it. Words later in the message don't count, so
+// "0 errors" or a file named error.rs stays info.
+fn classify_stderr(line: &str) -> &'static str {
+    let is_timestamp = |token: &str| {
+        token.chars().any(|c| c.is_ascii_digit())
+            && token.chars().all(|c| c.is_ascii_digit() || "-:./,TZ+[]()".contains(c))
+    };
+    let Some(first) = line.split_whitespace().find(|token| !is_timestamp(token)) else {
+        return "info";
+    };
+    if first == "Traceback" || (first == "thread" && line.contains("' panicked")) {
+        return "error";
+    }
+
+    let word: String = first
+        .trim_start_matches(['[', '(', '<'])
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect();
+    match word.to_ascii_uppercase().as_str() {
+        "ERROR" | "ERR" | "FATAL" | "CRITICAL" | "PANIC" | "SEVERE" => "error",
+        "WARN" | "WARNING" => "warn",
+        _ => "info",
+    }
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level {
+        "error" => 2,
+        "warn" => 1,
+        _ => 0,
+    }
+}
+
+// Consecutive stderr lines that form one message, e.g. a panic plus its backtrace
+struct StderrBlock {
+    lines: Vec<String>,
+    level: &'static str,
+    in_traceback: bool, // Python: indented frames until the unindented exception line
+}
+
+impl StderrBlock {
+    fn new(line: String) -> Self {
+        StderrBlock {
+            level: classify_stderr(&line),
+            in_traceback: line.starts_with("Traceback"),
+            lines: vec![line],
+        }
+    }
+
+    // Takes `line` if it continues this block
+    fn absorb(&mut self, line: &str) -> bool {
+        if self.lines.len() >= MAX_STDERR_BLOCK_LINES {
+            return false;
+        }
+        let indented = line.starts_with([' ', '\t']);
+        let continues = indented
+            || CONTINUATION_PREFIXES.iter().any(|p| line.starts_with(p))
+            || self.in_traceback
+            // Rust prints the panic message on its own line after "panicked at"
+            || (self.lines.len() == 1 && self.lines[0].contains("panicked at"));
+        if !continues {
+            return false;
+        }
+        if self.in_traceback && !indented {
+            self.in_traceback = false; // the exception line closes the traceback
+        }
+        let level = classify_stderr(line);
+        if level_rank(level) > level_rank(self.level) {
+            self.level = level;
+        }
+        self.lines.push(line.to_string());
+        true
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct LspProgress {
+    pub token: String,
+    pub title: String,
+    pub message: Option<String>,
+    pub percentage: Option<u32>,
+    pub done: bool,
+}
+
+#[derive(Serialize, Clone)]
+struct LspProgressEvent {
+    server_id: String,
+    #[serde(flatten)]
+    progress: LspProgress,
+}
+
+// Folds a `$/progress` notification into the per-token state. Title only arrives with
+// "begin", so later reports reuse it. Returns the updated entry to emit.
+fn apply_progress(progress: &Mutex<HashMap<String, LspProgress>>, message: &str) -> Option<LspProgress> {
+    let parsed: serde_json::Value = serde_json::from_str(message).ok()?;
+    if parsed["method"] != "$/progress" {
+        return None;
+    }
+    let params = &parsed["params"];
+    let token = match &params["token"] {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let value = &params["value"];
+    let text = value["message"].as_str().map(|m| m.to_string());
+    let percentage = value["percentage"].as_u64().map(|p| p.min(100) as u32);
+
+    let mut progress = progress.lock().unwrap();
+    match value["kind"].as_str()? {
+        "begin" => {
+            let entry = LspProgress {
+                token: token.clone(),
+                title: value["title"].as_str().unwrap_or("").to_string(),
+                message: text,
+                percentage,
+                done: false,
+            };
+            progress.insert(token, entry.clone());
+            Some(entry)
+        }
+        "report" => {
+            let entry = progress.get_mut(&token)?;
+            if text.is_some() {
+                entry.message = text;
+            }
+            if percentage.is_some() {
+                entry.percentage = percentage;
+            }
+            Some(entry.clone())
+        }
+        "end" => {
+            let mut entry = progress.remove(&token)?;
+            entry.message = text.or(entry.message);
+            entry.done = true;
+            Some(entry)
+        }
+        _ => None,
+    }
 }
 
 #[derive(Serialize, Clone)]
@@ -41,8 +245,57 @@ pub fn lsp_start<R: Runtime>(
     command: String,
     args: Vec<String>,
     cwd: Option<String>,
+    track_progress: Option<bool>,
 ) -> Result<(), String> {
-    let mut sessions = state.sessions.lock().unwrap();
+    let launch = LspLaunch {
+        command,
+        args,
+        cwd,
+        track_progress: track_progress.unwrap_or(false),
+    };
+    spawn_server(app, &state.sessions, &server_id, &launch)?;
+    state.launches.lock().unwrap().insert(server_id, launch);
+    Ok(())
+}
+
+/// Kills the server (if it's still running) and starts it again with the command, args
+/// and cwd it was originally started with. Events keep flowing on the same channels.
+#[tauri::command(async)]
+pub fn lsp_restart<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, LspState>,
+    server_id: String,
+) -> Result<(), String> {
+    let launch = state
+        .launches
+        .lock()
+        .unwrap()
+        .get(&server_id)
+        .cloned()
+        .ok_or(format!("Server {} was never started", server_id))?;
+
+    let session = state.sessions.lock().unwrap().remove(&server_id);
+    if let Some(session) = session {
+        stop_session(session);
+    }
+    spawn_server(app, &state.sessions, &server_id, &launch)
+}
+
+fn spawn_server<R: Runtime>(
+    app: AppHandle<R>,
+    sessions_ref: &Arc<Mutex<HashMap<String, LspSession>>>,
+    server_id: &str,
+    launch: &LspLaunch,
+) -> Result<(), String> {
+    let LspLaunch {
+        command,
+        args,
+        cwd,
+        track_progress,
+    } = launch;
+    let server_id = server_id.to_string();
+
+    let mut sessions = sessions_ref.lock().unwrap();
     if sessions.contains_key(&server_id) {
         return Err(format!("Server {} already running", server_id));
     }
@@ -60,8 +313,8 @@ pub fn lsp_start<R: Runtime>(
 
     #[cfg(not(target_os = "windows"))]
     let mut cmd = {
-        let mut c = Command::new(&command);
-        c.args(&args);
+        let mut c = Command::new(command);
+        c.args(args);
         c
     };
 
@@ -69,7 +322,7 @@ pub fn lsp_start<R: Runtime>(
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
 
-    if let Some(ref cwd) = cwd {
+    if let Some(cwd) = cwd {
         cmd.current_dir(cwd);
     }
 
@@ -82,19 +335,26 @@ pub fn lsp_start<R: Runtime>(
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
 
     let stdin = Arc::new(Mutex::new(Box::new(stdin) as Box<dyn Write + Send>));
+    let progress = if *track_progress {
+        Some(Arc::new(Mutex::new(HashMap::new())))
+    } else {
+        None
+    };
 
-    sessions.insert(
-        server_id.clone(),
-        LspSession {
-            child,
-            stdin: stdin.clone(),
-        },
-    );
+    let stopped = Arc::new(AtomicBool::new(false));
+    let child = Arc::new(Mutex::new(child));
+    // Never sent on; the reader dropping it is what tells the exit watcher stdout closed
+    let (stdout_closed_tx, stdout_eof) = mpsc::channel::<()>();
 
     // Stdout reader: parse JSON-RPC Content-Length framed messages
     let app_stdout = app.clone();
     let sid_stdout = server_id.clone();
-    thread::spawn(move || {
+    let stopped_stdout = stopped.clone();
+    let progress_stdout = progress.clone();
+    let pending: PendingRequests = Arc::new(Mutex::new(HashMap::new()));
+    let pending_stdout = pending.clone();
+    let stdout_reader = thread::spawn(move || {
+        let _stdout_closed = stdout_closed_tx;
         let mut reader = BufReader::new(stdout);
         loop {
             let mut content_length: usize = 0;
@@ -123,7 +383,42 @@ pub fn lsp_start<R: Runtime>(
                 return;
             }
 
+            if stopped_stdout.load(Ordering::SeqCst) {
+                return;
+            }
             let message = String::from_utf8_lossy(&body).to_string();
+
+            // Responses to lsp_request go back to the caller, not out as events
+            if message.contains(REQUEST_ID_PREFIX) {
+                if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&message) {
+                    let waiter = match (parsed["id"].as_str(), parsed.get("method")) {
+                        (Some(id), None) if id.starts_with(REQUEST_ID_PREFIX) => {
+                            pending_stdout.lock().unwrap().remove(id)
+                        }
+                        _ => None,
+                    };
+                    if let Some(waiter) = waiter {
+                        let _ = waiter.send(parsed);
+                        continue;
+                    }
+                }
+            }
+
+            // Raw messages still go out below; this is an extra, pre-digested view
+            if let Some(ref progress) = progress_stdout {
+                if message.contains("$/progress") {
+                    if let Some(update) = apply_progress(progress, &message) {
+                        let _ = app_stdout.emit(
+                            &format!("lsp-progress:{}", sid_stdout),
+                            LspProgressEvent {
+                                server_id: sid_stdout.clone(),
+                                progress: update,
+                            },
+                        );
+                    }
+                }
+            }
+
             let _ = app_stdout.emit(
                 &format!("lsp-message:{}", sid_stdout),
                 LspMessageEvent {
@@ -134,95 +429,371 @@ pub fn lsp_start<R: Runtime>(
         }
     });
 
-    // Stderr reader: forward server log output
+    // Stderr reader: forward server log output, tagged with a level, tracebacks merged
     let app_stderr = app.clone();
     let sid_stderr = server_id.clone();
-    thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            match line {
+    let stopped_stderr = stopped.clone();
+    // Lines are read on their own thread so a pending block can be flushed once the
+    // server goes quiet, rather than waiting for a line that may never come
+    let (line_tx, line_rx) = mpsc::channel::<String>();
+    let stderr_lines = thread::spawn(move || {
+        for line in BufReader::new(stderr).lines() {
+            let Ok(line) = line else { break };
+            if line_tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let stderr_reader = thread::spawn(move || {
+        let emit = |block: StderrBlock| {
+            if stopped_stderr.load(Ordering::SeqCst) {
+                return;
+            }
+            let _ = app_stderr.emit(
+                &format!("lsp-error:{}", sid_stderr),
+                LspErrorEvent {
+                    server_id: sid_stderr.clone(),
+                    error: block.lines.join("\n"),
+                    level: block.level,
+                },
+            );
+        };
+
+        let mut pending: Option<StderrBlock> = None;
+        loop {
+            match line_rx.recv_timeout(STDERR_COALESCE_WINDOW) {
                 Ok(line) => {
-                    let _ = app_stderr.emit(
-                        &format!("lsp-error:{}", sid_stderr),
-                        LspErrorEvent {
-                            server_id: sid_stderr.clone(),
-                            error: line,
-                        },
-                    );
+                    if pending.as_mut().is_some_and(|block| block.absorb(&line)) {
+                        continue;
+                    }
+                    if let Some(block) = pending.replace(StderrBlock::new(line)) {
+                        emit(block);
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if let Some(block) = pending.take() {
+                        emit(block);
+                    }
+                    if stopped_stderr.load(Ordering::SeqCst) {
+                        break;
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    if let Some(block) = pending.take() {
+                        emit(block);
+                    }
+                    break;
                 }
-                Err(_) => break,
             }
         }
     });
 
-    // Exit watcher
+    sessions.insert(
+        server_id.clone(),
+        LspSession {
+            child: child.clone(),
+            stdin: stdin.clone(),
+            open_documents: Arc::new(Mutex::new(HashMap::new())),
+            progress,
+            readers: vec![stdout_reader, stderr_lines, stderr_reader],
+            stopped: stopped.clone(),
+            pending,
+        },
+    );
+
+    // Exit watcher. Stdout closing is the prompt signal that the server is going away;
+    // the timeout is a fallback for servers whose stdout outlives them (held open by a
+    // grandchild). Neither path holds the sessions lock while waiting.
     let app_exit = app.clone();
     let sid_exit = server_id.clone();
-    let sessions_ref = state.sessions.clone();
+    let sessions_ref = sessions_ref.clone();
     thread::spawn(move || {
-        // Wait a moment then check if the child is still in our map
-        loop {
-            thread::sleep(std::time::Duration::from_secs(2));
-            let mut sessions = sessions_ref.lock().unwrap();
-            if let Some(session) = sessions.get_mut(&sid_exit) {
-                match session.child.try_wait() {
-                    Ok(Some(status)) => {
-                        let _ = app_exit.emit(
-                            &format!("lsp-exit:{}", sid_exit),
-                            LspExitEvent {
-                                server_id: sid_exit.clone(),
-                                code: status.code(),
-                            },
-                        );
-                        sessions.remove(&sid_exit);
-                        return;
-                    }
-                    Ok(None) => {} // Still running
-                    Err(_) => {
-                        sessions.remove(&sid_exit);
-                        return;
-                    }
-                }
+        let mut stdout_closed = false;
+        let code = loop {
+            if stdout_closed {
+                thread::sleep(Duration::from_millis(50));
             } else {
-                return; // Already removed
+                stdout_closed = matches!(
+                    stdout_eof.recv_timeout(EXIT_FALLBACK_INTERVAL),
+                    Err(mpsc::RecvTimeoutError::Disconnected)
+                );
+            }
+            // Stopped on purpose (lsp_stop / lsp_restart / lsp_reconcile), nothing to report
+            if stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            match child.lock().unwrap().try_wait() {
+                Ok(Some(status)) => break status.code(),
+                Ok(None) => continue,
+                Err(_) => break None,
             }
+        };
+
+        // Remove only our own session: after a restart the id may belong to a newer one.
+        // Whoever takes it out of the map is the one that reports it, so this fires once.
+        let removed = {
+            let mut sessions = sessions_ref.lock().unwrap();
+            let ours = sessions
+                .get(&sid_exit)
+                .is_some_and(|s| Arc::ptr_eq(&s.stopped, &stopped));
+            ours && sessions.remove(&sid_exit).is_some()
+        };
+        if removed {
+            let _ = app_exit.emit(
+                &format!("lsp-exit:{}", sid_exit),
+                LspExitEvent {
+                    server_id: sid_exit.clone(),
+                    code,
+                },
+            );
         }
     });
 
     Ok(())
 }
 
+fn write_message(stdin: &Mutex<Box<dyn Write + Send>>, message: &str) -> Result<(), String> {
+    let mut stdin = stdin.lock().unwrap();
+    let header = format!("Content-Length: {}\r\n\r\n", message.len());
+    stdin
+        .write_all(header.as_bytes())
+        .map_err(|e| e.to_string())?;
+    stdin
+        .write_all(message.as_bytes())
+        .map_err(|e| e.to_string())?;
+    stdin.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Keep open_documents in sync with didOpen/didClose sent by the frontend.
+// A document pre-opened by lsp_warm is closed first so the server never sees a double open.
+fn track_document(session: &LspSession, message: &str) -> Result<(), String> {
+    if !message.contains("textDocument/didOpen") && !message.contains("textDocument/didClose") {
+        return Ok(());
+    }
+    let parsed: serde_json::Value = match serde_json::from_str(message) {
+        Ok(v) => v,
+        Err(_) => return Ok(()),
+    };
+    let uri = match parsed["params"]["textDocument"]["uri"].as_str() {
+        Some(uri) => uri.to_string(),
+        None => return Ok(()),
+    };
+
+    let mut docs = session.open_documents.lock().unwrap();
+    match parsed["method"].as_str() {
+        Some("textDocument/didOpen") => {
+            if docs.get(&uri) == Some(&true) {
+                let close = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/didClose",
+                    "params": { "textDocument": { "uri": uri } },
+                });
+                write_message(&session.stdin, &close.to_string())?;
+            }
+            docs.insert(uri, false);
+        }
+        Some("textDocument/didClose") => {
+            docs.remove(&uri);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Frames and writes one JSON-RPC message. With `strict`, the body is parsed first and a
+/// malformed one is rejected before anything reaches the server, which would otherwise
+/// lose its place in the stream for good.
 #[tauri::command]
 pub fn lsp_send(
     state: tauri::State<'_, LspState>,
     server_id: String,
     message: String,
+    strict: Option<bool>,
 ) -> Result<(), String> {
+    if strict.unwrap_or(false) {
+        serde_json::from_str::<serde_json::Value>(&message)
+            .map_err(|e| format!("Invalid JSON-RPC message: {}", e))?;
+    }
+
     let sessions = state.sessions.lock().unwrap();
     let session = sessions
         .get(&server_id)
         .ok_or(format!("Server {} not found", server_id))?;
 
-    let mut stdin = session.stdin.lock().unwrap();
-    let header = format!("Content-Length: {}\r\n\r\n", message.len());
-    stdin
-        .write_all(header.as_bytes())
-        .map_err(|e| e.to_string())?;
-    stdin
-        .write_all(message.as_bytes())
-        .map_err(|e| e.to_string())?;
-    stdin.flush().map_err(|e| e.to_string())?;
-    Ok(())
+    track_document(session, &message)?;
+    write_message(&session.stdin, &message)
 }
 
+/// Sends a JSON-RPC request and resolves with its `result`, so callers don't have to
+/// match ids themselves. Rejects with the server's `error` object (as JSON), when the
+/// server stops, or after `timeout_ms` (30s by default).
 #[tauri::command]
-pub fn lsp_stop(state: tauri::State<'_, LspState>, server_id: String) -> Result<(), String> {
-    let mut sessions = state.sessions.lock().unwrap();
-    if let Some(mut session) = sessions.remove(&server_id) {
-        let _ = session.child.kill();
-        let _ = session.child.wait();
+pub async fn lsp_request(
+    state: tauri::State<'_, LspState>,
+    server_id: String,
+    method: String,
+    params: Option<serde_json::Value>,
+    timeout_ms: Option<u64>,
+) -> Result<serde_json::Value, String> {
+    let (stdin, pending) = {
+        let sessions = state.sessions.lock().unwrap();
+        let session = sessions
+            .get(&server_id)
+            .ok_or(format!("Server {} not found", server_id))?;
+        (session.stdin.clone(), session.pending.clone())
+    };
+
+    let timeout = timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+    let response = send_request(&stdin, &pending, &method, params, timeout)
+        .await
+        .map_err(|e| match e {
+            RequestError::Write(e) => e,
+            RequestError::Stopped => format!("Server {} stopped before responding to {}", server_id, method),
+            RequestError::Timeout => format!("timeout: {} got no response after {}ms", method, timeout.as_millis()),
+        })?;
+
+    match response.get("error") {
+        Some(error) => Err(error.to_string()),
+        None => Ok(response.get("result").cloned().unwrap_or(serde_json::Value::Null)),
     }
-    Ok(())
+}
+
+enum RequestError {
+    Write(String),
+    Stopped,
+    Timeout,
+}
+
+// Writes a request with a fresh id and waits for the stdout reader to hand back the
+// response carrying that id
+async fn send_request(
+    stdin: &Mutex<Box<dyn Write + Send>>,
+    pending: &PendingRequests,
+    method: &str,
+    params: Option<serde_json::Value>,
+    timeout: Duration,
+) -> Result<serde_json::Value, RequestError> {
+    let id = format!("{}{}", REQUEST_ID_PREFIX, NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed));
+    let mut request = serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method });
+    if let Some(params) = params {
+        request["params"] = params;
+    }
+
+    // Register before writing so a fast response can't arrive unclaimed
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    pending.lock().unwrap().insert(id.clone(), tx);
+    if let Err(e) = write_message(stdin, &request.to_string()) {
+        pending.lock().unwrap().remove(&id);
+        return Err(RequestError::Write(e));
+    }
+
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(response)) => Ok(response),
+        Ok(Err(_)) => Err(RequestError::Stopped),
+        Err(_) => {
+            pending.lock().unwrap().remove(&id);
+            Err(RequestError::Timeout)
+        }
+    }
+}
+
+/// Sends didOpen for each file so the server starts indexing before the user gets there.
+/// Returns the number of documents queued.
+#[tauri::command]
+pub fn lsp_warm(
+    state: tauri::State<'_, LspState>,
+    server_id: String,
+    file_paths: Vec<String>,
+) -> Result<usize, String> {
+    let (stdin, open_documents) = {
+        let sessions = state.sessions.lock().unwrap();
+        let session = sessions
+            .get(&server_id)
+            .ok_or(format!("Server {} not found", server_id))?;
+        (session.stdin.clone(), session.open_documents.clone())
+    };
+
+    let mut pending = Vec::new();
+    {
+        let docs = open_documents.lock().unwrap();
+        for path in file_paths {
+            let uri = match tauri::Url::from_file_path(&path) {
+                Ok(u) => u.to_string(),
+                Err(_) => continue,
+            };
+            if !docs.contains_key(&uri) {
+                pending.push((path, uri));
+            }
+        }
+    }
+
+    let queued = pending.len();
+    thread::spawn(move || {
+        for (path, uri) in pending {
+            let text = match std::fs::read_to_string(&path) {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+            {
+                // Re-check: the frontend may have opened it while we were throttled
+                let mut docs = open_documents.lock().unwrap();
+                if docs.contains_key(&uri) {
+                    continue;
+                }
+                docs.insert(uri.clone(), true);
+            }
+
+            let notification = serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": {
+                    "textDocument": {
+                        "uri": uri,
+                        "languageId": crate::language::detect(std::path::Path::new(&path)),
+                        "version": 1,
+                        "text": text,
+                    }
+                },
+            });
+            if write_message(&stdin, &notification.to_string()).is_err() {
+                return;
+            }
+
+            // Throttle so a large batch doesn't flood the server
+            thread::sleep(std::time::Duration::from_millis(50));
+        }
+    });
+
+    Ok(queued)
+}
+
+/// Stops a server the way the LSP lifecycle expects: `shutdown` request, `exit`
+/// notification, and a kill only if it's still running after that. Servers like
+/// rust-analyzer use the grace period to flush caches. `force` skips straight to the kill.
+#[tauri::command]
+pub async fn lsp_stop(state: tauri::State<'_, LspState>, server_id: String, force: Option<bool>) -> Result<(), String> {
+    // Take it out first so nothing else talks to it and the waits below don't hold the lock
+    let session = state.sessions.lock().unwrap().remove(&server_id);
+    let Some(session) = session else {
+        return Ok(());
+    };
+
+    if !force.unwrap_or(false) {
+        // Errors here just mean the server is already gone or wedged; the kill covers both
+        let _ = send_request(&session.stdin, &session.pending, "shutdown", None, SHUTDOWN_TIMEOUT).await;
+        let exit = serde_json::json!({ "jsonrpc": "2.0", "method": "exit" });
+        let _ = write_message(&session.stdin, &exit.to_string());
+
+        let deadline = Instant::now() + EXIT_TIMEOUT;
+        while Instant::now() < deadline && matches!(session.child.lock().unwrap().try_wait(), Ok(None)) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    tokio::task::spawn_blocking(move || stop_session(session))
+        .await
+        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -230,3 +801,46 @@ pub fn lsp_list(state: tauri::State<'_, LspState>) -> Result<Vec<String>, String
     let sessions = state.sessions.lock().unwrap();
     Ok(sessions.keys().cloned().collect())
 }
+
+/// After a frontend reload, stops every server the frontend no longer tracks so the
+/// next start doesn't spawn a duplicate. Returns the ids that are still running.
+#[tauri::command(async)]
+pub fn lsp_reconcile(state: tauri::State<'_, LspState>, active_ids: Vec<String>) -> Result<Vec<String>, String> {
+    let (orphaned, running) = {
+        let mut sessions = state.sessions.lock().unwrap();
+
+        let orphaned: Vec<LspSession> = sessions
+            .keys()
+            .filter(|id| !active_ids.contains(id))
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|id| sessions.remove(&id))
+            .collect();
+
+        // Drop sessions whose process already died but the exit watcher hasn't reaped yet
+        sessions.retain(|_, session| matches!(session.child.lock().unwrap().try_wait(), Ok(None)));
+
+        (orphaned, sessions.keys().cloned().collect())
+    };
+
+    for session in orphaned {
+        stop_session(session);
+    }
+    Ok(running)
+}
+
+/// Work-done progress currently in flight for a server started with `track_progress`.
+#[tauri::command]
+pub fn lsp_get_progress(state: tauri::State<'_, LspState>, server_id: String) -> Result<Vec<LspProgress>, String> {
+    let sessions = state.sessions.lock().unwrap();
+    let session = sessions
+        .get(&server_id)
+        .ok_or(format!("Server {} not found", server_id))?;
+
+    Ok(session
+        .progress
+        .as_ref()
+        .map(|p| p.lock().unwrap().values().cloned().collect())
+        .unwrap_or_default())
+}
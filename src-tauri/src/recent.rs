@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::Manager;
+
+const MAX_RECENT_ITEMS: usize = 50;
+
+// Serializes read-modify-write cycles across windows
+static RECENT_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RecentItem {
+    pub path: String,
+    pub kind: String, // "file" | "folder"
+    pub opened_at: i64, // epoch millis
+}
+
+fn recent_path(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    use tauri::path::BaseDirectory;
+
+    handle
+        .path()
+        .resolve("recent.json", BaseDirectory::AppConfig)
+        .map_err(|e| e.to_string())
+}
+
+fn load(path: &Path) -> Vec<RecentItem> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+// Write to a sibling temp file then rename, so a crash never leaves a truncated list
+fn save(path: &Path, items: &[RecentItem]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string_pretty(items).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn add_recent_item(handle: tauri::AppHandle, path: String, kind: String) -> Result<(), String> {
+    let _guard = RECENT_LOCK.lock().unwrap();
+    let file = recent_path(&handle)?;
+
+    let mut items = load(&file);
+    items.retain(|item| item.path != path);
+    items.insert(
+        0,
+        RecentItem {
+            path,
+            kind,
+            opened_at: chrono::Utc::now().timestamp_millis(),
+        },
+    );
+    items.truncate(MAX_RECENT_ITEMS);
+
+    save(&file, &items)
+}
+
+#[tauri::command]
+pub fn get_recent_items(handle: tauri::AppHandle, limit: Option<usize>) -> Result<Vec<RecentItem>, String> {
+    let _guard = RECENT_LOCK.lock().unwrap();
+    let file = recent_path(&handle)?;
+
+    let mut items = load(&file);
+    let before = items.len();
+    items.retain(|item| Path::new(&item.path).exists());
+    if items.len() != before {
+        save(&file, &items)?;
+    }
+
+    items.truncate(limit.unwrap_or(MAX_RECENT_ITEMS));
+    Ok(items)
+}
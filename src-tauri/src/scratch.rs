@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+// Leftovers from sessions that never reached a clean exit are swept on startup
+const SCRATCH_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+pub struct ScratchState {
+    pub files: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScratchFile {
+    pub path: String,
+    pub size: u64,
+    pub modified: Option<i64>, // epoch millis
+}
+
+fn scratch_dir(handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join("scratch");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// Creates an empty, uniquely named file in the app cache dir to back an untitled buffer.
+#[tauri::command]
+pub fn create_scratch_file(
+    handle: tauri::AppHandle,
+    state: tauri::State<'_, ScratchState>,
+    extension: Option<String>,
+) -> Result<String, String> {
+    let dir = scratch_dir(&handle)?;
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let name = match extension.as_deref().map(|e| e.trim_start_matches('.')) {
+        Some(ext) if !ext.is_empty() => {
+            if ext.contains(['/', '\\']) {
+                return Err(format!("Invalid extension: {}", ext));
+            }
+            format!("scratch-{}.{}", &id[..8], ext)
+        }
+        _ => format!("scratch-{}", &id[..8]),
+    };
+
+    let path = dir.join(name);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+
+    state.files.lock().unwrap().push(path.clone());
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Scratch files created during this session that still exist on disk.
+#[tauri::command]
+pub fn list_scratch_files(state: tauri::State<'_, ScratchState>) -> Vec<ScratchFile> {
+    let mut files = state.files.lock().unwrap();
+    files.retain(|p| p.exists());
+
+    files
+        .iter()
+        .filter_map(|p| {
+            let meta = fs::metadata(p).ok()?;
+            Some(ScratchFile {
+                path: p.to_string_lossy().to_string(),
+                size: meta.len(),
+                modified: meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as i64),
+            })
+        })
+        .collect()
+}
+
+/// Removes scratch files older than the TTL. Called once during setup.
+pub fn sweep_expired(handle: &tauri::AppHandle) {
+    let Ok(dir) = scratch_dir(handle) else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| now.duration_since(t).ok())
+            .is_some_and(|age| age > SCRATCH_TTL);
+        if expired {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Deletes every scratch file this session created. Called when the app exits.
+pub fn cleanup_session(handle: &tauri::AppHandle) {
+    let state = handle.state::<ScratchState>();
+    for path in state.files.lock().unwrap().drain(..) {
+        let _ = fs::remove_file(path);
+    }
+}
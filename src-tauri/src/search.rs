@@ -0,0 +1,702 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Runtime};
+
+pub struct ActiveSearch {
+    pub child: Child,
+    pub query: String,
+    pub started_at: i64, // epoch millis
+    pub cancelled: Arc<AtomicBool>, // checked by the reader before each emit
+}
+
+impl ActiveSearch {
+    fn cancel(mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+pub struct SearchState {
+    pub searches: Arc<Mutex<HashMap<String, ActiveSearch>>>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchInfo {
+    pub id: String,
+    pub query: String,
+    pub started_at: i64,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchMatch {
+    pub path: String,
+    pub line_number: u64,
+    pub column: u64,
+    pub line_text: String,
+    pub match_text: String,
+}
+
+#[derive(Serialize, Clone)]
+struct SearchResultEvent {
+    search_id: String,
+    #[serde(flatten)]
+    result: SearchMatch,
+}
+
+#[derive(Serialize, Clone)]
+struct SearchDoneEvent {
+    search_id: String,
+    count: usize,
+    truncated: bool, // stopped early at max_results
+    cancelled: bool,
+}
+
+// One `rg --json` line -> the matches it contains (one per submatch)
+fn parse_rg_line(line: &str, cwd: &str) -> Vec<SearchMatch> {
+    let parsed: serde_json::Value = match serde_json::from_str(line) {
+        Ok(v) => v,
+        Err(_) => return vec![],
+    };
+
+    if parsed["type"] != "match" {
+        return vec![];
+    }
+
+    let data = &parsed["data"];
+    let path_text = data["path"]["text"].as_str().unwrap_or("");
+    let line_number = data["line_number"].as_u64().unwrap_or(0);
+    let line_text = data["lines"]["text"].as_str().unwrap_or("").trim_end().to_string();
+    let full_path = Path::new(cwd).join(path_text).to_string_lossy().to_string();
+
+    let mut results = Vec::new();
+    if let Some(submatches) = data["submatches"].as_array() {
+        for sm in submatches {
+            results.push(SearchMatch {
+                path: full_path.clone(),
+                line_number,
+                column: sm["start"].as_u64().unwrap_or(0),
+                line_text: line_text.clone(),
+                match_text: sm["match"]["text"].as_str().unwrap_or("").to_string(),
+            });
+        }
+    }
+    results
+}
+
+// Upper bound on matches held in memory when sorting before truncation
+const MAX_SORTED_MATCHES: usize = 100_000;
+
+/// Runs rg and streams each match as a `search-result` event, followed by one
+/// `search-done`, all tagged with `search_id`. The collected matches are also
+/// returned for callers that just await the whole result. With `sort` = "path",
+/// matches are ordered by (path, line, column) before the `max_results` cut, so
+/// events arrive only once rg has finished.
+#[tauri::command(async)]
+#[allow(clippy::too_many_arguments)] // one flat argument per invoke() key
+pub fn ripgrep_search<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, SearchState>,
+    query: String,
+    cwd: String,
+    case_sensitive: bool,
+    regex: bool,
+    max_results: Option<u32>,
+    search_id: Option<String>,
+    include_ignored: Option<bool>,
+    include_hidden: Option<bool>,
+    globs: Option<Vec<String>>,
+    sort: Option<String>,
+) -> Result<Vec<SearchMatch>, String> {
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let sort_by_path = match sort.as_deref() {
+        None | Some("none") => false,
+        Some("path") => true,
+        Some(other) => return Err(format!("Unknown sort: {}", other)),
+    };
+
+    let globs = globs.unwrap_or_default();
+    if let Some(bad) = globs.iter().find(|g| g.contains(['\n', '\r'])) {
+        return Err(format!("Invalid glob: {:?}", bad));
+    }
+
+    let max = max_results.unwrap_or(500) as usize;
+    let mut cmd = std::process::Command::new("rg");
+    cmd.arg("--json")
+        .arg("--max-count").arg("100")  // max matches per file
+        .arg("--max-filesize").arg("1M");
+    
+    if !cwd.is_empty() {
+        cmd.current_dir(&cwd);
+    }
+
+    if !case_sensitive {
+        cmd.arg("--ignore-case");
+    }
+    if !regex {
+        cmd.arg("--fixed-strings");
+    }
+    if include_ignored.unwrap_or(false) {
+        cmd.arg("--no-ignore");
+    }
+    if include_hidden.unwrap_or(false) {
+        cmd.arg("--hidden");
+    }
+    // "*.rs" scopes the search, "!*.lock" excludes
+    for glob in &globs {
+        cmd.arg("--glob").arg(glob);
+    }
+
+    cmd.arg("--").arg(&query);
+
+    cmd.stdout(Stdio::piped()).stderr(Stdio::null());
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to run rg: {}", e))?;
+    let rg_stdout = child.stdout.take().ok_or("Failed to capture rg stdout")?;
+
+    // Register so the search can be listed and cancelled while rg runs. Reusing an id
+    // replaces the previous search, so a stale query never emits after a newer one.
+    let search_id = search_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let previous = state.searches.lock().unwrap().insert(
+        search_id.clone(),
+        ActiveSearch {
+            child,
+            query: query.clone(),
+            started_at: chrono::Utc::now().timestamp_millis(),
+            cancelled: cancelled.clone(),
+        },
+    );
+    if let Some(previous) = previous {
+        previous.cancel();
+    }
+
+    let mut results: Vec<SearchMatch> = Vec::new();
+    let mut truncated = false;
+    let mut read_error = None;
+    // Sorting needs everything rg finds, so only the memory bound applies while reading
+    let collect_limit = if sort_by_path { MAX_SORTED_MATCHES } else { max };
+
+    for line in BufReader::new(rg_stdout).lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(e) => {
+                read_error = Some(e.to_string());
+                break;
+            }
+        };
+
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        for result in parse_rg_line(&line, &cwd) {
+            if results.len() >= collect_limit {
+                truncated = true;
+                break;
+            }
+            if !sort_by_path {
+                let _ = app.emit(
+                    "search-result",
+                    SearchResultEvent {
+                        search_id: search_id.clone(),
+                        result: result.clone(),
+                    },
+                );
+            }
+            results.push(result);
+        }
+        if truncated {
+            break;
+        }
+    }
+
+    if sort_by_path {
+        results.sort_by(|a, b| {
+            a.path
+                .cmp(&b.path)
+                .then(a.line_number.cmp(&b.line_number))
+                .then(a.column.cmp(&b.column))
+        });
+        if results.len() > max {
+            results.truncate(max);
+            truncated = true;
+        }
+        if !cancelled.load(Ordering::Relaxed) {
+            for result in &results {
+                let _ = app.emit(
+                    "search-result",
+                    SearchResultEvent {
+                        search_id: search_id.clone(),
+                        result: result.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    // A cancelled search has already been removed (and killed); the entry under this id
+    // may now belong to a newer search. Hitting the cap kills rg here instead of letting
+    // it finish the whole tree.
+    let active = {
+        let mut searches = state.searches.lock().unwrap();
+        let ours = searches
+            .get(&search_id)
+            .is_some_and(|a| Arc::ptr_eq(&a.cancelled, &cancelled));
+        if ours {
+            searches.remove(&search_id)
+        } else {
+            None
+        }
+    };
+    let cancelled = match active {
+        Some(mut active) => {
+            if truncated {
+                let _ = active.child.kill();
+            }
+            let _ = active.child.wait();
+            false
+        }
+        None => true,
+    };
+
+    let _ = app.emit(
+        "search-done",
+        SearchDoneEvent {
+            search_id,
+            count: results.len(),
+            truncated,
+            cancelled,
+        },
+    );
+
+    if cancelled {
+        return Err("Search cancelled".to_string());
+    }
+    if let Some(e) = read_error {
+        return Err(e);
+    }
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub fn list_active_searches(state: tauri::State<'_, SearchState>) -> Result<Vec<SearchInfo>, String> {
+    let searches = state.searches.lock().unwrap();
+    Ok(searches
+        .iter()
+        .map(|(id, s)| SearchInfo {
+            id: id.clone(),
+            query: s.query.clone(),
+            started_at: s.started_at,
+        })
+        .collect())
+}
+
+/// Kills every running rg process. Returns how many searches were cancelled.
+#[tauri::command]
+pub fn cancel_all_searches(state: tauri::State<'_, SearchState>) -> Result<usize, String> {
+    let mut searches = state.searches.lock().unwrap();
+    let count = searches.len();
+    for (_, active) in searches.drain() {
+        active.cancel();
+    }
+    Ok(count)
+}
+
+/// Kills the rg process for `search_id`. Returns false if it had already finished.
+#[tauri::command]
+pub fn cancel_search(state: tauri::State<'_, SearchState>, search_id: String) -> Result<bool, String> {
+    let active = state.searches.lock().unwrap().remove(&search_id);
+    Ok(match active {
+        Some(active) => {
+            active.cancel();
+            true
+        }
+        None => false,
+    })
+}
+
+// Keep undo data for the most recent bulk replaces only; each holds full file contents
+const MAX_UNDO_ENTRIES: usize = 20;
+
+pub struct ReplacedFile {
+    path: String,
+    original: String,
+    replaced_hash: String, // sha256 of what we wrote, to detect later external edits
+}
+
+pub struct ReplaceUndo {
+    token: String,
+    files: Vec<ReplacedFile>,
+}
+
+pub struct ReplaceUndoState {
+    pub entries: Arc<Mutex<Vec<ReplaceUndo>>>, // oldest first
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileReplaceResult {
+    pub path: String,
+    pub count: u32,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ProjectReplaceResult {
+    pub undo_token: Option<String>, // None when nothing was replaced
+    pub files: Vec<FileReplaceResult>,
+}
+
+// Files under `cwd` matching `search`, as found by rg (respects .gitignore, skips binaries)
+fn files_with_matches(cwd: &str, search: &str, regex: bool, globs: &[String]) -> Result<Vec<String>, String> {
+    let mut cmd = std::process::Command::new("rg");
+    cmd.arg("--files-with-matches");
+    if !regex {
+        cmd.arg("--fixed-strings");
+    }
+    for glob in globs {
+        cmd.arg("--glob").arg(glob);
+    }
+    let output = cmd
+        .arg("--")
+        .arg(search)
+        .current_dir(cwd)
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run rg: {}", e))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|rel| Path::new(cwd).join(rel).to_string_lossy().to_string())
+        .collect())
+}
+
+/// Replaces every match of `search` in the project (optionally limited to `globs`) and
+/// returns per-file counts plus a token that `undo_replace` can use to put the files back.
+/// With `regex`, `replace` may reference capture groups as `$1` or `${name}`.
+#[tauri::command(async)]
+pub fn search_replace_project(
+    state: tauri::State<'_, ReplaceUndoState>,
+    cwd: String,
+    search: String,
+    replace: String,
+    regex: Option<bool>,
+    globs: Option<Vec<String>>,
+) -> Result<ProjectReplaceResult, String> {
+    if search.is_empty() {
+        return Err("Search string is empty".to_string());
+    }
+    let regex = regex.unwrap_or(false);
+    let re = if regex {
+        Some(regex::Regex::new(&search).map_err(|e| format!("Invalid regex: {}", e))?)
+    } else {
+        None
+    };
+
+    let mut results = Vec::new();
+    let mut undo: Vec<ReplacedFile> = Vec::new();
+
+    for path in files_with_matches(&cwd, &search, regex, globs.as_deref().unwrap_or_default())? {
+        let bytes = match std::fs::read(&path) {
+            Ok(b) => b,
+            Err(_) => continue, // vanished since rg saw it
+        };
+        // rg already skips most binaries; this catches anything it let through
+        if bytes.contains(&0) {
+            continue;
+        }
+        let Ok(original) = String::from_utf8(bytes) else {
+            continue;
+        };
+
+        let (count, replaced) = match &re {
+            Some(re) => (
+                re.find_iter(&original).count() as u32,
+                re.replace_all(&original, replace.as_str()).into_owned(),
+            ),
+            None => (original.matches(&search).count() as u32, original.replace(&search, &replace)),
+        };
+        if count == 0 || replaced == original {
+            continue;
+        }
+
+        if let Err(e) = crate::write_atomic(Path::new(&path), replaced.as_bytes()) {
+            // Put back what we already changed so a failure never leaves a half-applied rename
+            for file in &undo {
+                let _ = crate::write_atomic(Path::new(&file.path), file.original.as_bytes());
+            }
+            return Err(format!("{}: {}", path, e));
+        }
+
+        results.push(FileReplaceResult {
+            path: path.clone(),
+            count,
+        });
+        undo.push(ReplacedFile {
+            path,
+            original,
+            replaced_hash: crate::sha256_hex(replaced.as_bytes()),
+        });
+    }
+
+    if undo.is_empty() {
+        return Ok(ProjectReplaceResult {
+            undo_token: None,
+            files: results,
+        });
+    }
+
+    let token = uuid::Uuid::new_v4().to_string();
+    let mut entries = state.entries.lock().unwrap();
+    entries.push(ReplaceUndo {
+        token: token.clone(),
+        files: undo,
+    });
+    if entries.len() > MAX_UNDO_ENTRIES {
+        entries.remove(0);
+    }
+
+    Ok(ProjectReplaceResult {
+        undo_token: Some(token),
+        files: results,
+    })
+}
+
+/// Restores every file touched by a `search_replace_project` call. Refuses (and keeps
+/// the token) if any of them was modified after the replace. Returns the restored paths;
+/// if some can't be written, the token stays valid for retrying just those.
+#[tauri::command]
+pub fn undo_replace(state: tauri::State<'_, ReplaceUndoState>, token: String) -> Result<Vec<String>, String> {
+    let mut entries = state.entries.lock().unwrap();
+    let idx = entries
+        .iter()
+        .position(|e| e.token == token)
+        .ok_or("Nothing to undo for this token")?;
+
+    let conflicts: Vec<&str> = entries[idx]
+        .files
+        .iter()
+        .filter(|f| {
+            let current = std::fs::read(&f.path).unwrap_or_default();
+            crate::sha256_hex(&current) != f.replaced_hash
+        })
+        .map(|f| f.path.as_str())
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(format!("conflict: changed since replace: {}", conflicts.join(", ")));
+    }
+
+    let mut restored = Vec::new();
+    let mut failed = Vec::new();
+    for file in &entries[idx].files {
+        match crate::write_atomic(Path::new(&file.path), file.original.as_bytes()) {
+            Ok(()) => restored.push(file.path.clone()),
+            Err(e) => failed.push(format!("{}: {}", file.path, e)),
+        }
+    }
+    if failed.is_empty() {
+        entries.remove(idx);
+        return Ok(restored);
+    }
+
+    // Keep the token for the files still to restore, so the undo can be retried; the
+    // restored ones are dropped or the retry would see them as changed
+    entries[idx].files.retain(|f| !restored.contains(&f.path));
+    Err(format!("failed to restore: {}", failed.join("; ")))
+}
+
+// Go-to-file: stop walking huge trees rather than hang the picker
+const MAX_WALK_FILES: usize = 200_000;
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_CONSECUTIVE: i32 = 12;
+const BONUS_BOUNDARY: i32 = 8; // start of a path segment, word or camelCase hump
+const BONUS_FILE_NAME: i32 = 4;
+const PENALTY_GAP: i32 = 1; // per skipped character between two matches
+
+#[derive(Serialize, Clone)]
+pub struct FileMatch {
+    pub path: String,
+    pub relative_path: String,
+    pub score: i32,
+    pub indices: Vec<usize>, // char indices into relative_path that matched
+}
+
+// Best subsequence alignment of `query` (already lowercased) in `path`, rewarding
+// consecutive runs and segment starts and penalizing gaps. O(query * path).
+fn fuzzy_score(query: &[char], path: &str) -> Option<(i32, Vec<usize>)> {
+    const NONE: i32 = i32::MIN / 2;
+
+    let chars: Vec<char> = path.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let (m, n) = (query.len(), chars.len());
+    if m == 0 {
+        return Some((0, vec![]));
+    }
+    if m > n {
+        return None;
+    }
+
+    // Cheap reject before the DP
+    let mut qi = 0;
+    for c in &lower {
+        if qi < m && *c == query[qi] {
+            qi += 1;
+        }
+    }
+    if qi < m {
+        return None;
+    }
+
+    let file_name_start = chars.iter().rposition(|c| *c == '/').map_or(0, |i| i + 1);
+    let bonus = |j: usize| -> i32 {
+        let mut b = 0;
+        let boundary = j == 0
+            || matches!(chars[j - 1], '/' | '\\' | '_' | '-' | '.' | ' ')
+            || (chars[j - 1].is_lowercase() && chars[j].is_uppercase());
+        if boundary {
+            b += BONUS_BOUNDARY;
+        }
+        if j >= file_name_start {
+            b += BONUS_FILE_NAME;
+        }
+        b
+    };
+
+    let mut dp = vec![vec![NONE; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+
+    for j in 0..n {
+        if lower[j] == query[0] {
+            dp[0][j] = SCORE_MATCH + bonus(j);
+        }
+    }
+
+    for i in 1..m {
+        // Best dp[i-1][k] - gap penalty over k <= j-2, carried along as j advances
+        let mut best = NONE;
+        let mut best_k = 0;
+        for j in 1..n {
+            if j >= 2 {
+                best -= PENALTY_GAP;
+                let cand = dp[i - 1][j - 2] - PENALTY_GAP;
+                if cand > best {
+                    best = cand;
+                    best_k = j - 2;
+                }
+            }
+            if lower[j] != query[i] {
+                continue;
+            }
+
+            let consecutive = if dp[i - 1][j - 1] > NONE {
+                dp[i - 1][j - 1] + BONUS_CONSECUTIVE
+            } else {
+                NONE
+            };
+            let (prev, k) = if consecutive >= best {
+                (consecutive, j - 1)
+            } else {
+                (best, best_k)
+            };
+            if prev <= NONE / 2 {
+                continue;
+            }
+            dp[i][j] = prev + SCORE_MATCH + bonus(j);
+            from[i][j] = k;
+        }
+    }
+
+    let (end, score) = dp[m - 1]
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| **s > NONE / 2)
+        .max_by_key(|(_, s)| **s)
+        .map(|(j, s)| (j, *s))?;
+
+    let mut indices = vec![0; m];
+    let mut j = end;
+    for i in (0..m).rev() {
+        indices[i] = j;
+        j = from[i][j];
+    }
+    Some((score, indices))
+}
+
+fn walk_files(root: &Path, dir: &Path, out: &mut Vec<String>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.flatten() {
+        if out.len() >= MAX_WALK_FILES {
+            return;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let file_type = match entry.file_type() {
+            Ok(t) => t,
+            Err(_) => continue,
+        };
+
+        if file_type.is_dir() {
+            if !crate::IGNORED_DIRS.contains(&name.as_str()) {
+                walk_files(root, &entry.path(), out);
+            }
+        } else if !crate::IGNORED_FILES.contains(&name.as_str()) {
+            if let Ok(rel) = entry.path().strip_prefix(root) {
+                out.push(rel.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+}
+
+/// Fuzzy "go to file": every file under `cwd` whose relative path contains `query`
+/// as a subsequence, best matches first.
+#[tauri::command(async)]
+pub fn find_files(cwd: String, query: String, max_results: Option<usize>) -> Result<Vec<FileMatch>, String> {
+    let root = Path::new(&cwd);
+    if !root.is_dir() {
+        return Err("Not a directory".into());
+    }
+
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+
+    let mut files = Vec::new();
+    walk_files(root, root, &mut files);
+
+    let mut matches: Vec<FileMatch> = files
+        .into_iter()
+        .filter_map(|rel| {
+            let (score, indices) = fuzzy_score(&query, &rel)?;
+            Some(FileMatch {
+                path: root.join(&rel).to_string_lossy().to_string(),
+                relative_path: rel,
+                score,
+                indices,
+            })
+        })
+        .collect();
+
+    // Equal scores: shorter paths first, then alphabetical for a stable order
+    matches.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.relative_path.len().cmp(&b.relative_path.len()))
+            .then_with(|| a.relative_path.cmp(&b.relative_path))
+    });
+    matches.truncate(max_results.unwrap_or(100));
+    Ok(matches)
+}
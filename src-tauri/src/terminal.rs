@@ -1,5 +1,6 @@
-use portable_pty::{native_pty_system, CommandBuilder, MasterPty, PtyPair, PtySize};
-use std::collections::HashMap;
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtyPair, PtySize};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -8,17 +9,155 @@ use tauri::{AppHandle, Emitter, Runtime};
 pub struct TerminalSession {
     pub writer: Arc<Mutex<Box<dyn Write + Send>>>,
     pub pty_pair: PtyPair,
+    pub killer: Box<dyn ChildKiller + Send + Sync>, // usable while another thread waits on the child
 }
 
+const SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+type Scrollback = Arc<Mutex<VecDeque<u8>>>; // most recent raw output, capped
+
 pub struct TerminalState {
     pub sessions: Arc<Mutex<HashMap<String, TerminalSession>>>,
+    // Kept apart from `sessions` so the output of a terminal that exited on its own can
+    // still be read; dropped by kill_terminal or release_terminal_buffer
+    pub buffers: Arc<Mutex<HashMap<String, Scrollback>>>,
+}
+
+#[derive(Serialize, Clone)]
+struct TerminalExitEvent {
+    id: String,
+    code: u32,
+}
+
+// Decodes as much of `buf` as is final, replacing invalid bytes, and leaves a trailing
+// incomplete sequence in place so a character split across two reads isn't mangled
+fn take_utf8(buf: &mut Vec<u8>) -> String {
+    let mut out = String::new();
+    let mut start = 0;
+    while start < buf.len() {
+        match std::str::from_utf8(&buf[start..]) {
+            Ok(s) => {
+                out.push_str(s);
+                start = buf.len();
+            }
+            Err(e) => {
+                let valid = start + e.valid_up_to();
+                out.push_str(std::str::from_utf8(&buf[start..valid]).unwrap());
+                match e.error_len() {
+                    Some(len) => {
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        start = valid + len;
+                    }
+                    None => {
+                        start = valid; // incomplete, wait for the next read
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    buf.drain(..start);
+    out
+}
+
+// Forward PTY output to the frontend as `terminal-data:{id}` until the PTY closes
+fn spawn_reader<R: Runtime>(
+    app: AppHandle<R>,
+    id: String,
+    mut reader: Box<dyn Read + Send>,
+    scrollback: Scrollback,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let event = format!("terminal-data:{}", id);
+        let mut buffer = [0u8; 4096];
+        let mut pending = Vec::new();
+        while let Ok(n) = reader.read(&mut buffer) {
+            if n == 0 {
+                break;
+            }
+            {
+                let mut scrollback = scrollback.lock().unwrap();
+                scrollback.extend(&buffer[..n]);
+                let excess = scrollback.len().saturating_sub(SCROLLBACK_BYTES);
+                scrollback.drain(..excess);
+            }
+            pending.extend_from_slice(&buffer[..n]);
+            let data = take_utf8(&mut pending);
+            if !data.is_empty() {
+                let _ = app.emit(&event, data);
+            }
+        }
+        // Whatever is left can never be completed now
+        if !pending.is_empty() {
+            let _ = app.emit(&event, String::from_utf8_lossy(&pending).to_string());
+        }
+    })
+}
+
+// The user's login shell from the environment, else the platform default
+fn default_shell() -> String {
+    #[cfg(target_os = "windows")]
+    let (var, fallback) = ("COMSPEC", "powershell.exe");
+    #[cfg(not(target_os = "windows"))]
+    let (var, fallback) = ("SHELL", "bash");
+
+    std::env::var(var)
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
 }
 
+// Waits for the process to end, then emits `terminal-exit:{id}` with its exit code
+fn spawn_exit_watcher<R: Runtime>(
+    app: AppHandle<R>,
+    sessions: Arc<Mutex<HashMap<String, TerminalSession>>>,
+    id: String,
+    mut child: Box<dyn portable_pty::Child + Send + Sync>,
+    writer: Arc<Mutex<Box<dyn Write + Send>>>, // identifies our session under `id`
+    reader_handle: thread::JoinHandle<()>,
+) {
+    thread::spawn(move || {
+        let code = child.wait().map(|status| status.exit_code()).unwrap_or(1);
+
+        // Dropping the session closes the PTY, which lets the reader drain and hit EOF,
+        // so all output is emitted before the exit event. If it's no longer ours,
+        // kill_terminal took it and the id may already belong to a new terminal, so
+        // stay quiet rather than report an exit for that one.
+        let ours = {
+            let mut sessions = sessions.lock().unwrap();
+            let ours = sessions.get(&id).is_some_and(|s| Arc::ptr_eq(&s.writer, &writer));
+            if ours {
+                sessions.remove(&id);
+            }
+            ours
+        };
+        let _ = reader_handle.join();
+        if !ours {
+            return;
+        }
+
+        let _ = app.emit(
+            &format!("terminal-exit:{}", id),
+            TerminalExitEvent {
+                id: id.clone(),
+                code,
+            },
+        );
+    });
+}
+
+/// Opens an interactive shell in a PTY. `shell` defaults to $SHELL (%COMSPEC% on
+/// Windows); `env` is added on top of the inherited environment. `terminal-exit:{id}`
+/// fires when the shell ends.
 #[tauri::command]
 pub fn spawn_terminal<R: Runtime>(
     app: AppHandle<R>,
     state: tauri::State<'_, TerminalState>,
     id: String,
+    shell: Option<String>,
+    args: Option<Vec<String>>,
+    cwd: Option<String>,
+    env: Option<HashMap<String, String>>,
 ) -> Result<(), String> {
     let pty_system = native_pty_system();
 
@@ -32,17 +171,21 @@ pub fn spawn_terminal<R: Runtime>(
         })
         .map_err(|e| e.to_string())?;
 
-    #[cfg(target_os = "windows")]
-    let shell = "powershell.exe";
-    #[cfg(not(target_os = "windows"))]
-    let shell = "bash";
+    let shell = shell.filter(|s| !s.is_empty()).unwrap_or_else(default_shell);
 
-    let mut cmd = CommandBuilder::new(shell);
+    let mut cmd = CommandBuilder::new(&shell);
+    cmd.args(args.unwrap_or_default());
+    if let Some(ref cwd) = cwd {
+        cmd.cwd(cwd);
+    }
+    for (key, value) in env.unwrap_or_default() {
+        cmd.env(key, value);
+    }
 
-    let _child = pty_pair
+    let child = pty_pair
         .slave
         .spawn_command(cmd)
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| format!("Failed to spawn {}: {}", shell, e))?;
 
     let reader = pty_pair
         .master
@@ -52,33 +195,74 @@ pub fn spawn_terminal<R: Runtime>(
 
     let writer = Arc::new(Mutex::new(writer));
     let sessions = state.sessions.clone();
+    let scrollback = Arc::new(Mutex::new(VecDeque::new()));
 
     sessions.lock().unwrap().insert(
         id.clone(),
         TerminalSession {
             writer: writer.clone(),
             pty_pair,
+            killer: child.clone_killer(),
         },
     );
+    state.buffers.lock().unwrap().insert(id.clone(), scrollback.clone());
 
-    let app_clone = app.clone();
-    let id_clone = id.clone();
-
-    thread::spawn(move || {
-        let mut reader = reader;
-        let mut buffer = [0u8; 4096];
-        while let Ok(n) = reader.read(&mut buffer) {
-            if n == 0 {
-                break;
-            }
-            let data = String::from_utf8_lossy(&buffer[..n]).to_string();
-            let _ = app_clone.emit(&format!("terminal-data:{}", id_clone), data);
-        }
-    });
+    let reader_handle = spawn_reader(app.clone(), id.clone(), reader, scrollback);
+    spawn_exit_watcher(app, sessions, id, child, writer, reader_handle);
 
     Ok(())
 }
 
+/// Kills the terminal's process and closes its PTY, which ends the reader thread, and
+/// drops its scrollback. No `terminal-exit` follows. Returns false if there was no
+/// running terminal with this id.
+#[tauri::command]
+pub fn kill_terminal(state: tauri::State<'_, TerminalState>, id: String) -> Result<bool, String> {
+    let session = state.sessions.lock().unwrap().remove(&id);
+    state.buffers.lock().unwrap().remove(&id);
+    let Some(mut session) = session else {
+        return Ok(false);
+    };
+    // Already exited is fine; dropping the session below releases the PTY either way
+    let _ = session.killer.kill();
+    drop(session);
+    Ok(true)
+}
+
+/// The last `max_bytes` (default: all retained, up to 1MB) of what the terminal has
+/// printed, escape sequences included. Still available after the process exits, until
+/// kill_terminal or release_terminal_buffer.
+#[tauri::command]
+pub fn read_terminal_buffer(
+    state: tauri::State<'_, TerminalState>,
+    id: String,
+    max_bytes: Option<usize>,
+) -> Result<String, String> {
+    let scrollback = state
+        .buffers
+        .lock()
+        .unwrap()
+        .get(&id)
+        .cloned()
+        .ok_or(format!("Terminal {} does not exist", id))?;
+
+    let scrollback = scrollback.lock().unwrap();
+    let start = scrollback.len().saturating_sub(max_bytes.unwrap_or(SCROLLBACK_BYTES));
+    let tail: Vec<u8> = scrollback
+        .range(start..)
+        .copied()
+        // Don't start in the middle of a multi-byte character
+        .skip_while(|b| b & 0b1100_0000 == 0b1000_0000)
+        .collect();
+    Ok(String::from_utf8_lossy(&tail).to_string())
+}
+
+/// Frees the scrollback of a terminal that has exited. Returns false if there was none.
+#[tauri::command]
+pub fn release_terminal_buffer(state: tauri::State<'_, TerminalState>, id: String) -> bool {
+    state.buffers.lock().unwrap().remove(&id).is_some()
+}
+
 #[tauri::command]
 pub fn write_to_terminal(
     state: tauri::State<'_, TerminalState>,
@@ -86,13 +270,14 @@ pub fn write_to_terminal(
     data: String,
 ) -> Result<(), String> {
     let sessions = state.sessions.lock().unwrap();
-    if let Some(session) = sessions.get(&id) {
-        let mut writer = session.writer.lock().unwrap();
-        writer
-            .write_all(data.as_bytes())
-            .map_err(|e| e.to_string())?;
-        writer.flush().map_err(|e| e.to_string())?;
-    }
+    let session = sessions
+        .get(&id)
+        .ok_or(format!("Terminal {} has exited or does not exist", id))?;
+    let mut writer = session.writer.lock().unwrap();
+    writer
+        .write_all(data.as_bytes())
+        .map_err(|e| e.to_string())?;
+    writer.flush().map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -118,3 +303,96 @@ pub fn resize_terminal(
     }
     Ok(())
 }
+
+#[derive(Deserialize)]
+pub struct TerminalSize {
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// Applies every resize under a single lock so a layout change can't interleave
+/// with other terminal calls and leave some sessions mis-sized.
+#[tauri::command]
+pub fn resize_terminals(
+    state: tauri::State<'_, TerminalState>,
+    sizes: HashMap<String, TerminalSize>,
+) -> Result<(), String> {
+    let sessions = state.sessions.lock().unwrap();
+    let mut errors = Vec::new();
+
+    for (id, size) in sizes {
+        if let Some(session) = sessions.get(&id) {
+            if let Err(e) = session.pty_pair.master.resize(PtySize {
+                rows: size.rows,
+                cols: size.cols,
+                pixel_width: 0,
+                pixel_height: 0,
+            }) {
+                errors.push(format!("{}: {}", id, e));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// Runs a single program directly in a PTY (no shell). Output streams as
+/// `terminal-data:{id}` and `terminal-exit:{id}` fires with the exit code when it ends.
+#[tauri::command]
+pub fn spawn_command_terminal<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, TerminalState>,
+    id: String,
+    command: String,
+    args: Vec<String>,
+    cwd: Option<String>,
+) -> Result<(), String> {
+    let pty_system = native_pty_system();
+    let pty_pair = pty_system
+        .openpty(PtySize {
+            rows: 24,
+            cols: 80,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut cmd = CommandBuilder::new(&command);
+    cmd.args(&args);
+    if let Some(ref cwd) = cwd {
+        cmd.cwd(cwd);
+    }
+
+    let mut child = pty_pair
+        .slave
+        .spawn_command(cmd)
+        .map_err(|e| format!("Failed to spawn {}: {}", command, e))?;
+
+    let reader = pty_pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| e.to_string())?;
+    let writer = pty_pair.master.take_writer().map_err(|e| e.to_string())?;
+
+    let writer = Arc::new(Mutex::new(writer));
+    let sessions = state.sessions.clone();
+    let scrollback = Arc::new(Mutex::new(VecDeque::new()));
+    sessions.lock().unwrap().insert(
+        id.clone(),
+        TerminalSession {
+            writer: writer.clone(),
+            pty_pair,
+            killer: child.clone_killer(),
+        },
+    );
+    state.buffers.lock().unwrap().insert(id.clone(), scrollback.clone());
+
+    let reader_handle = spawn_reader(app.clone(), id.clone(), reader, scrollback);
+    spawn_exit_watcher(app, sessions, id, child, writer, reader_handle);
+
+    Ok(())
+}
@@ -33,8 +33,8 @@ class BlameWidget extends WidgetType {
     }
 }
 
-function formatRelativeTime(timestamp: string): string {
-    const seconds = Math.floor(Date.now() / 1000 - parseInt(timestamp));
+function formatRelativeTime(timestamp: number): string {
+    const seconds = Math.floor(Date.now() / 1000 - timestamp);
     if (seconds < 60) return "just now";
     const minutes = Math.floor(seconds / 60);
     if (minutes < 60) return `${minutes}m ago`;
@@ -110,7 +110,7 @@ const blameDecorations = StateField.define<DecorationSet>({
 
         return Decoration.set([
             Decoration.widget({
-                widget: new BlameWidget(blame.author, formatRelativeTime(blame.date)),
+                widget: new BlameWidget(blame.author, formatRelativeTime(blame.timestamp)),
                 side: 1
             }).range(line.to)
         ]);
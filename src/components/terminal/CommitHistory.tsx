@@ -90,7 +90,7 @@ function GitGraph({ nodes, links, rowPositions }: { nodes: GraphNode[], links: G
 }
 
 function CommitDetailPopup({ commit, position, details }: { commit: CommitEntry, position: { x: number, y: number }, details?: CommitDetails }) {
-    const dateObj = new Date(parseInt(commit.date) * 1000);
+    const dateObj = new Date(commit.timestamp * 1000);
     const fullDate = dateObj.toLocaleString('en-US', {
         month: 'short',
         day: 'numeric',
@@ -14,7 +14,12 @@ export interface CommitEntry {
     hash: string;
     message: string;
     author: string;
-    date: string;
+    author_email: string;
+    committer: string;
+    committer_email: string;
+    date: string; // RFC3339 with offset
+    timestamp: number;
+    offset_minutes: number;
     parent_hashes: string[];
 }
 
@@ -99,7 +104,9 @@ class GitService {
 
 export interface BlameEntry {
     author: string;
-    date: string; // timestamp
+    date: string; // RFC3339 with offset
+    timestamp: number;
+    offset_minutes: number;
     hash: string;
 }
 
@@ -107,7 +114,12 @@ export interface CommitDetails {
     hash: string;
     message: string;
     author: string;
-    date: string;
+    author_email: string;
+    committer: string;
+    committer_email: string;
+    date: string; // RFC3339 with offset
+    timestamp: number;
+    offset_minutes: number;
     files_changed: number;
     insertions: number;
     deletions: number;
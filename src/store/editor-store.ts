@@ -335,6 +335,20 @@ async function persistSettings(path: string, settings: any) {
   }
 }
 
+// Stops the backend's watch_file poller once no workspace has the file open anymore.
+// Untitled, browser, diff and ted:// tabs are never watched.
+function releaseFileWatch(path: string) {
+  if (path.startsWith("untitled-") || path.startsWith("diff:") || path.includes("://")) return;
+  const stillOpen = Object.values(state.workspaces).some((ws) =>
+    ws.tabs.some((t) => t.path === path),
+  );
+  if (!stillOpen) {
+    invoke("unwatch_file", { path }).catch((err) =>
+      console.error("Failed to unwatch", path, err),
+    );
+  }
+}
+
 function updateTab(path: string, update: Partial<TabState>) {
   const tabs = state.tabs.map((t) =>
     t.path === path ? { ...t, ...update } : t,
@@ -475,6 +489,7 @@ export const editorStore = {
       activeWorkspaceId: nextActiveId,
       ...extraStateUpdates
     });
+    removed.tabs.forEach((t) => releaseFileWatch(t.path));
   },
 
   newFile() {
@@ -568,6 +583,7 @@ export const editorStore = {
       activeTabPath = newIdx >= 0 ? tabs[newIdx].path : null;
     }
     dispatch("CLOSE_TAB", { tabs, activeTabPath }, { path });
+    releaseFileWatch(path);
     telemetry.log("file_close", { path });
   },
 